@@ -0,0 +1,38 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Uses `trim-margin` in a `no_std` crate.
+//! Building it for a target without `std` fails if `trim-margin` depends on `std`.
+
+#![no_std]
+
+extern crate alloc;
+extern crate trim_margin;
+
+use alloc::string::String;
+use trim_margin::{MarginError, MarginTrimmable, TrimIntoError, TrimOptions};
+
+
+pub fn trim(text: &str) -> Result<String, MarginError> {
+    text.try_trim_margin()
+}
+
+pub fn trim_opts(text: &str, options: &TrimOptions) -> Option<String> {
+    text.trim_margin_opts(options)
+}
+
+pub fn trim_into(text: &str, buffer: &mut [u8]) -> Result<usize, TrimIntoError> {
+    text.trim_margin_into_slice("|", buffer)
+}
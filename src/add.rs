@@ -0,0 +1,139 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::string::String;
+use line_ending::{LineBreaks, LineEnding};
+use options::{Indentation, Policy};
+
+
+/// Renders `input` as a margin block which is trimmed back to `input` by the same `prefix`, `right_delimiter` and `policy`.
+///
+/// Every line is indented by `indent` spaces, unless the `policy` allows no indentation, followed by the `prefix`
+/// and terminated by the `right_delimiter`.
+/// The block is surrounded by as many blank lines as the `policy` removes at the start and the end.
+/// If the `policy` terminates the last line, the final line break of `input` is left to the trimming,
+/// which takes it from the trailing blank lines or, if there are none, from the preceding line.
+pub(crate) fn add_margin(input: &str, indent: usize, prefix: &str, right_delimiter: Option<&str>, policy: &Policy) -> String {
+    let indent = if policy.indentation == Indentation::None { 0 } else { indent };
+    let mut line_break = policy.line_ending.terminator("\n");
+    let mut with_margin = String::with_capacity(input.len() + 2 * (indent + prefix.len() + 1));
+    for _ in 0..policy.leading_blank_lines {
+        with_margin.push_str(line_break);
+    }
+
+    let mut rest = input;
+    let mut last_line = "";
+    loop {
+        let (line, ending, next) = match policy.line_breaks.split_line(rest) {
+            Some(split) => split,
+            None => (rest, "", ""),
+        };
+        with_margin.extend(core::iter::repeat_n(' ', indent));
+        with_margin.push_str(prefix);
+        with_margin.push_str(line);
+        with_margin.extend(right_delimiter);
+        if ending.is_empty() {
+            last_line = line;
+            break;
+        }
+        if next.is_empty() && policy.terminates_last_line() {
+            line_break = policy.line_ending.terminator(ending);
+            break;
+        }
+        with_margin.push_str(policy.line_ending.terminator(ending));
+        rest = next;
+    }
+
+    // a trailing `\r` followed by `\n` would be taken for a `\r\n` line break
+    let ends_with_cr = policy.line_breaks == LineBreaks::LfOrCrLf && right_delimiter.is_none() && last_line.ends_with('\r');
+    let line_break = if ends_with_cr && policy.line_ending == LineEnding::Preserve { "\r\n" } else { line_break };
+    for _ in 0..policy.trailing_blank_lines {
+        with_margin.push_str(line_break);
+    }
+    with_margin
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use galvanic_assert::matchers::*;
+    use proptest::prelude::*;
+    use super::*;
+    use options::Chomping;
+    use MarginTrimmable;
+    use TrimOptions;
+
+    #[test]
+    fn should_surround_margin_block_with_blank_lines() {
+        assert_that!(&add_margin("first\n  second", 4, "|", None, &Policy::default()),
+                     eq("\n    |first\n    |  second\n".to_string()));
+    }
+
+    #[test]
+    fn should_add_configured_number_of_blank_lines() {
+        let policy = Policy { leading_blank_lines: 0, trailing_blank_lines: 2, ..Policy::default() };
+        assert_that!(&add_margin("first\r\nsecond", 0, "#", None, &policy), eq("#first\r\n#second\n\n".to_string()));
+    }
+
+    #[test]
+    fn should_honour_indentation_right_delimiter_and_trailing_newline() {
+        let options = TrimOptions::new().indentation(Indentation::None).right_delimiter("|").trailing_newline(true);
+        let with_margin = "first \nsecond\n".add_margin_opts(4, &options);
+        assert_that!(&with_margin, eq("\n|first |\n|second|\n".to_string()));
+        assert_that!(&with_margin.trim_margin_opts(&options), eq(Some("first \nsecond\n".to_string())));
+    }
+
+    proptest! {
+        #[test]
+        fn should_round_trip_through_trim_margin(text in "[ab |#>\t\r\n]{0,40}",
+                                                 indent in 0usize..8,
+                                                 prefix in prop::sample::select(vec!["|", "#", ">>", "//"])) {
+            let with_margin = text.add_margin(indent, prefix);
+            prop_assert_eq!(with_margin.trim_margin_with(prefix), Some(text));
+        }
+
+        #[test]
+        fn should_round_trip_through_trim_margin_opts(text in "[ab |\t\r\n]{0,40}",
+                                                      leading in 0usize..3,
+                                                      trailing in 0usize..3,
+                                                      line_breaks in prop::sample::select(vec![LineBreaks::LfOrCrLf, LineBreaks::Any]),
+                                                      indentation in prop::sample::select(vec![Indentation::Whitespace, Indentation::None]),
+                                                      right_delimiter in prop::option::of(prop::sample::select(vec!["|", "<"])),
+                                                      trailing_newline in any::<bool>(),
+                                                      keep in any::<bool>()) {
+            let mut options = TrimOptions::new()
+                .leading_blank_lines(leading)
+                .trailing_blank_lines(trailing)
+                .line_breaks(line_breaks)
+                .indentation(indentation)
+                .trailing_newline(trailing_newline);
+            if let Some(delimiter) = right_delimiter {
+                options = options.right_delimiter(delimiter);
+            }
+            if keep {
+                options = options.chomping(Chomping::Keep);
+            }
+            // the last line can only be terminated by the trimming if the text ends with a line break
+            let ends_with_line_break = text.ends_with('\n') || (line_breaks == LineBreaks::Any && text.ends_with('\r'));
+            // without trailing blank lines the final line break is the one of the preceding line
+            prop_assume!(!options.policy().terminates_last_line() || (ends_with_line_break && trailing > 0));
+            let with_margin = text.add_margin_opts(2, &options);
+            // strings without line break are not trimmed at all
+            prop_assume!(line_breaks.split_line(&with_margin).is_some());
+            prop_assert_eq!(with_margin.trim_margin_opts(&options), Some(text));
+        }
+    }
+}
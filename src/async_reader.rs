@@ -0,0 +1,330 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Asynchronous counterparts of `MarginReader` and `MarginLines` for `tokio`.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::mem;
+use core::pin::Pin;
+use core::str;
+use core::task::{Context, Poll};
+use error::MarginError;
+use futures_core::Stream;
+use reader::LineTrimmer;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+
+/// Appends the bytes of `inner` up to and including the next line break to `line`.
+///
+/// Nothing is appended at the end of the input. The bytes read so far are kept in `line` if the input is pending.
+fn poll_read_line<R: AsyncBufRead + Unpin>(inner: &mut R, line: &mut Vec<u8>, cx: &mut Context) -> Poll<io::Result<()>> {
+    loop {
+        let available = match Pin::new(&mut *inner).poll_fill_buf(cx) {
+            Poll::Ready(Ok(available)) => available,
+            Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+            Poll::Pending => return Poll::Pending,
+        };
+        if available.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+        let (len, complete) = match available.iter().position(|&b| b == b'\n') {
+            Some(idx) => (idx + 1, true),
+            None => (available.len(), false),
+        };
+        line.extend_from_slice(&available[..len]);
+        Pin::new(&mut *inner).consume(len);
+        if complete {
+            return Poll::Ready(Ok(()));
+        }
+    }
+}
+
+fn invalid_utf8(error: str::Utf8Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+
+/// An asynchronous reader which removes the margin of the text read from another reader on the fly.
+///
+/// It behaves like `MarginReader`, i.e., its output is identical to trimming the whole input at once
+/// and a line without margin prefix is reported as an `io::Error` of kind `InvalidData` wrapping the `MarginError`.
+/// Only a single line of the input is held in memory.
+#[derive(Debug)]
+pub struct AsyncMarginReader<R> {
+    inner: R,
+    trimmer: LineTrimmer,
+    line: Vec<u8>,
+    ending: Option<&'static str>,
+    output: String,
+    position: usize,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncMarginReader<R> {
+    /// Creates a reader which removes blanks and the `margin_prefix` from the lines read from `inner`.
+    pub fn new<M: AsRef<str>>(inner: R, margin_prefix: M) -> AsyncMarginReader<R> {
+        AsyncMarginReader {
+            inner,
+            trimmer: LineTrimmer::new(margin_prefix.as_ref()),
+            line: Vec::new(),
+            ending: None,
+            output: String::new(),
+            position: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R { &self.inner }
+
+    /// Returns the underlying reader.
+    ///
+    /// Input which has been read but not been returned yet is lost.
+    pub fn into_inner(self) -> R { self.inner }
+
+    /// Reads the next line of the input and appends its trimmed content to the output.
+    ///
+    /// After an error the input is ended, so the following lines are not read.
+    fn poll_next_line(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        match poll_read_line(&mut self.inner, &mut self.line, cx) {
+            Poll::Ready(Ok(())) => {},
+            Poll::Ready(Err(error)) => {
+                self.trimmer.abort();
+                return Poll::Ready(Err(error));
+            },
+            Poll::Pending => return Poll::Pending,
+        }
+        let mut line = mem::take(&mut self.line);
+        let trimmed = match str::from_utf8(&line) {
+            Ok(text) => self.trimmer.trim_line(text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) => {
+                self.trimmer.abort();
+                Err(invalid_utf8(error))
+            },
+        };
+        if let Some((content, ending)) = trimmed? {
+            self.output.extend(self.ending.take());
+            self.output.push_str(content);
+            self.ending = Some(ending).filter(|ending| !ending.is_empty());
+        }
+        line.clear();
+        self.line = line;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for AsyncMarginReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.position == this.output.len() {
+            this.output.clear();
+            this.position = 0;
+            while this.output.is_empty() && !this.trimmer.is_done() {
+                match this.poll_next_line(cx) {
+                    Poll::Ready(Ok(())) => {},
+                    Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+        Poll::Ready(Ok(&this.output.as_bytes()[this.position..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        let this = self.get_mut();
+        this.position = (this.position + amount).min(this.output.len());
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for AsyncMarginReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buffer: &mut ReadBuf) -> Poll<io::Result<()>> {
+        let len = match self.as_mut().poll_fill_buf(cx) {
+            Poll::Ready(Ok(available)) => {
+                let len = available.len().min(buffer.remaining());
+                buffer.put_slice(&available[..len]);
+                len
+            },
+            Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+            Poll::Pending => return Poll::Pending,
+        };
+        self.consume(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+
+/// An asynchronous stream of the trimmed lines read from another reader, i.e., the counterpart of `MarginLines`.
+///
+/// The lines are yielded without their line breaks. A line without margin prefix is yielded as a `MarginError`,
+/// as is a failure to read the input, which has the kind `ErrorKind::Io`. The stream ends after an error.
+#[derive(Debug)]
+pub struct AsyncMarginLines<R> {
+    inner: R,
+    trimmer: LineTrimmer,
+    line: Vec<u8>,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncMarginLines<R> {
+    /// Creates a stream of the lines read from `inner` with blanks and the `margin_prefix` removed.
+    pub fn new<M: AsRef<str>>(inner: R, margin_prefix: M) -> AsyncMarginLines<R> {
+        AsyncMarginLines { inner, trimmer: LineTrimmer::new(margin_prefix.as_ref()), line: Vec::new() }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R { &self.inner }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R { self.inner }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncMarginLines<R> {
+    type Item = Result<String, MarginError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.trimmer.is_done() {
+            match poll_read_line(&mut this.inner, &mut this.line, cx) {
+                Poll::Ready(Ok(())) => {},
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(this.trimmer.io_error(&error)))),
+                Poll::Pending => return Poll::Pending,
+            }
+            let mut line = mem::take(&mut this.line);
+            let trimmed = match str::from_utf8(&line) {
+                Ok(text) => this.trimmer.trim_line(text).map(|trimmed| trimmed.map(|(content, _)| content.to_string())),
+                Err(error) => Err(this.trimmer.io_error(&invalid_utf8(error))),
+            };
+            line.clear();
+            this.line = line;
+            match trimmed {
+                Ok(Some(content)) => return Poll::Ready(Some(Ok(content))),
+                Ok(None) => {},
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use core::future::Future;
+    use error::ErrorKind;
+    use galvanic_assert::matchers::*;
+    use proptest::prelude::*;
+    use std::future::poll_fn;
+    use std::thread;
+    use super::*;
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream};
+    use tokio::runtime;
+    use MarginTrimmable;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+
+    /// Returns the reading end of an in-memory pipe to which `input` is written in chunks of `capacity` bytes.
+    fn pipe(input: &str, capacity: usize) -> BufReader<DuplexStream> {
+        let (mut writer, reader) = duplex(capacity);
+        let input = input.to_string();
+        thread::spawn(move || block_on(writer.write_all(input.as_bytes())));
+        BufReader::with_capacity(capacity, reader)
+    }
+
+    fn read_trimmed(input: &str, capacity: usize) -> io::Result<String> {
+        let mut trimmed = String::new();
+        block_on(AsyncMarginReader::new(pipe(input, capacity), "|").read_to_string(&mut trimmed))?;
+        Ok(trimmed)
+    }
+
+    fn collect_lines(input: &str, capacity: usize) -> Vec<Result<String, MarginError>> {
+        let mut lines = AsyncMarginLines::new(pipe(input, capacity), "|");
+        let mut items = Vec::new();
+        block_on(poll_fn(|cx| loop {
+            match Pin::new(&mut lines).poll_next(cx) {
+                Poll::Ready(Some(item)) => items.push(item),
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Pending => return Poll::Pending,
+            }
+        }));
+        items
+    }
+
+    #[test]
+    fn should_trim_lines_read_from_duplex_stream() {
+        let txt = "\r\n    |first\r\n    |  second\n  ";
+        assert_that!(&read_trimmed(txt, 3).unwrap(), eq("first\r\n  second".to_string()));
+    }
+
+    #[test]
+    fn should_report_line_without_margin_as_io_error() {
+        let error = read_trimmed("\n  |first\n  second\n", 4).unwrap_err();
+        assert_that!(&error.kind(), eq(io::ErrorKind::InvalidData));
+        assert_that!(&error.to_string().starts_with("line 3: expected margin prefix"), eq(true));
+    }
+
+    #[test]
+    fn should_end_after_line_which_cannot_be_read() {
+        let mut reader = AsyncMarginReader::new(&b"\n |a\xff\n |b\n |c\n"[..], "|");
+        let mut trimmed = String::new();
+        let error = block_on(reader.read_to_string(&mut trimmed)).unwrap_err();
+        assert_that!(&error.kind(), eq(io::ErrorKind::InvalidData));
+        assert_that!(&block_on(reader.read_to_string(&mut trimmed)).unwrap(), eq(0));
+        assert_that!(&trimmed.is_empty(), eq(true));
+    }
+
+    #[test]
+    fn should_stream_trimmed_lines() {
+        let txt = "\n    |first\n    |\n    |  third\n";
+        assert_that!(&collect_lines(txt, 2),
+                     eq(vec![Ok("first".to_string()), Ok("".to_string()), Ok("  third".to_string())]));
+    }
+
+    #[test]
+    fn should_end_stream_after_line_without_margin() {
+        let lines = collect_lines("\n  |first\n  second\n  |third\n", 5);
+        assert_that!(&lines.len(), eq(2));
+        let error = lines[1].clone().unwrap_err();
+        assert_that!(&error.kind(), eq(ErrorKind::MissingPrefix));
+        assert_that!(&error.line(), eq(3));
+    }
+
+    #[test]
+    fn should_report_invalid_utf8_as_io_error_kind() {
+        let mut lines = AsyncMarginLines::new(&b"\n  |a\xff\n"[..], "|");
+        let item = block_on(poll_fn(|cx| Pin::new(&mut lines).poll_next(cx)));
+        let error = item.unwrap().unwrap_err();
+        assert_that!(&error.kind(), eq(ErrorKind::Io));
+        assert_that!(&error.io_error_kind(), eq(Some(io::ErrorKind::InvalidData)));
+        assert_that!(&error.line(), eq(2));
+    }
+
+    proptest! {
+        #[test]
+        fn should_read_like_trim_margin(text in "[ab |\t\r\n]{0,40}", capacity in 1usize..8) {
+            match text.try_trim_margin() {
+                Ok(trimmed) => prop_assert_eq!(read_trimmed(&text, capacity).ok(), Some(trimmed)),
+                Err(_) => prop_assert!(read_trimmed(&text, capacity).is_err()),
+            }
+        }
+
+        #[test]
+        fn should_stream_like_margin_lines(text in "[ab |\t\r\n]{0,40}", capacity in 1usize..8) {
+            let expected: Vec<_> = text.margin_lines("|").map(|line| line.map(|line| line.to_string())).collect();
+            prop_assert_eq!(collect_lines(&text, capacity), expected);
+        }
+    }
+}
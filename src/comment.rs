@@ -0,0 +1,164 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use error::MarginError;
+use lines::{is_blank, strip_margin, RawLine, RawLines};
+use options::Policy;
+use pattern::Margin;
+
+
+/// The syntax of a comment block whose comment leaders are removed by `MarginTrimmable::trim_comment`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// Rust outer doc comments starting with `///`.
+    RustOuterDoc,
+    /// Rust inner doc comments starting with `//!`.
+    RustInnerDoc,
+    /// C block comments delimited by `/*` or `/**` and `*/` whose lines start with `*`.
+    CBlock,
+    /// Shell, Python, etc. comments starting with `#`.
+    Hash,
+    /// SQL, Lua, Haskell, etc. comments starting with `--`.
+    DoubleDash,
+}
+
+impl CommentStyle {
+    /// The leader which starts every line of the comment.
+    fn leader(self) -> &'static str {
+        match self {
+            CommentStyle::RustOuterDoc => "///",
+            CommentStyle::RustInnerDoc => "//!",
+            CommentStyle::CBlock => "*",
+            CommentStyle::Hash => "#",
+            CommentStyle::DoubleDash => "--",
+        }
+    }
+
+    /// Returns the rest of `line` behind the delimiter opening the comment block, i.e., `/*`, `/**` or `/*!`.
+    fn strip_opening_delimiter(self, line: &str) -> Option<&str> {
+        if self != CommentStyle::CBlock {
+            return None;
+        }
+        let line = line.trim_start();
+        ["/**", "/*!", "/*"].iter().filter_map(|delimiter| line.strip_prefix(delimiter)).next()
+    }
+
+    /// Returns `line` in front of the delimiter `*/` closing the comment block without trailing blanks.
+    fn strip_closing_delimiter(self, line: &str) -> Option<&str> {
+        if self != CommentStyle::CBlock {
+            return None;
+        }
+        line.trim_end().strip_suffix("*/").map(str::trim_end)
+    }
+}
+
+
+/// Removes the comment delimiters and from every line the comment leader and a single following space.
+///
+/// Text behind the opening and in front of the closing delimiter is kept, lines consisting only of a delimiter are removed.
+pub(crate) fn trim_comment(input: &str, style: CommentStyle, policy: &Policy) -> Result<String, MarginError> {
+    let lines: Vec<RawLine> = match RawLines::new(input, policy) {
+        Some(lines) => lines.collect(),
+        None if is_blank(input) => return Ok(input.into()),
+        // a string without line break is a single comment line
+        None => vec![RawLine { number: 1, offset: 0, text: input, ending: "" }],
+    };
+
+    let last = lines.len().saturating_sub(1);
+    let mut trimmed = String::with_capacity(input.len());
+    let mut terminator = None;
+    for (idx, line) in lines.into_iter().enumerate() {
+        let closed = if idx == last { style.strip_closing_delimiter(line.text) } else { None };
+        let text = closed.unwrap_or(line.text);
+        let opened = if idx == 0 { style.strip_opening_delimiter(text) } else { None };
+        let content = match opened {
+            Some(rest) if is_blank(rest) => continue,
+            Some(rest) => rest,
+            // e.g. the `*` of a line `**/`
+            None if closed.is_some() && matches!(text.trim(), "" | "*") => continue,
+            None => strip_margin(RawLine { text, ..line }, &Margin::Literal(style.leader()), None, policy)?,
+        };
+
+        trimmed.extend(terminator);
+        trimmed.push_str(content.strip_prefix(' ').unwrap_or(content));
+        terminator = Some(policy.line_ending.terminator(line.ending));
+    }
+    Ok(trimmed)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use galvanic_assert::matchers::*;
+    use error::ErrorKind;
+    use super::*;
+
+    fn trim(input: &str, style: CommentStyle) -> Result<String, MarginError> {
+        trim_comment(input, style, &Policy::default())
+    }
+
+    #[test]
+    fn should_remove_leader_and_single_space() {
+        let txt = "
+            /// Returns the answer.
+            ///
+            ///     assert_eq!(answer(), 42);
+        ";
+        assert_that!(&trim(txt, CommentStyle::RustOuterDoc),
+                     eq(Ok("Returns the answer.\n\n    assert_eq!(answer(), 42);".to_string())));
+    }
+
+    #[test]
+    fn should_remove_delimiter_lines_of_block_comment() {
+        let txt = "/**\r\n * Copyright 2018\r\n *\r\n *  Licensed under ...\r\n */";
+        assert_that!(&trim(txt, CommentStyle::CBlock), eq(Ok("Copyright 2018\r\n\r\n Licensed under ...".to_string())));
+        let txt = "\n    /*\n     * Returns 42.\n     */\n";
+        assert_that!(&trim(txt, CommentStyle::CBlock), eq(Ok("Returns 42.".to_string())));
+    }
+
+    #[test]
+    fn should_trim_single_comment_line() {
+        assert_that!(&trim("/// hello", CommentStyle::RustOuterDoc), eq(Ok("hello".to_string())));
+        assert_that!(&trim("/* hello */", CommentStyle::CBlock), eq(Ok("hello".to_string())));
+        assert_that!(&trim("/**/", CommentStyle::CBlock), eq(Ok("".to_string())));
+    }
+
+    #[test]
+    fn should_keep_text_next_to_block_comment_delimiters() {
+        assert_that!(&trim("/** Returns 42.\n * more\n */", CommentStyle::CBlock), eq(Ok("Returns 42.\nmore".to_string())));
+        assert_that!(&trim("/**\n * text */", CommentStyle::CBlock), eq(Ok("text".to_string())));
+        assert_that!(&trim("/* first\n * last */\n", CommentStyle::CBlock), eq(Ok("first\nlast".to_string())));
+        assert_that!(&trim("/**\n * text\n **/", CommentStyle::CBlock), eq(Ok("text".to_string())));
+    }
+
+    #[test]
+    fn should_trim_line_comments_of_other_languages() {
+        assert_that!(&trim("# usage: run.sh\n#\n#   -h  help", CommentStyle::Hash),
+                     eq(Ok("usage: run.sh\n\n  -h  help".to_string())));
+        assert_that!(&trim("\n  -- Creates the table.\n  --Drops it first.\n", CommentStyle::DoubleDash),
+                     eq(Ok("Creates the table.\nDrops it first.".to_string())));
+    }
+
+    #[test]
+    fn should_report_line_without_comment_leader() {
+        let error = trim("//! crate docs\n/// item docs", CommentStyle::RustInnerDoc).unwrap_err();
+        assert_that!(&error.kind(), eq(ErrorKind::MissingPrefix));
+        assert_that!(&error.line(), eq(2));
+        assert_that!(&error.prefix(), eq("//!"));
+    }
+}
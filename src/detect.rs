@@ -0,0 +1,99 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use error::{ErrorKind, MarginError};
+use lines::{is_blank, RawLines};
+
+
+/// Returns the leading run of characters which are neither blank nor alphanumeric.
+fn prefix_token(content: &str) -> &str {
+    let end = content.find(|c: char| c.is_whitespace() || c.is_alphanumeric()).unwrap_or(content.len());
+    &content[..end]
+}
+
+/// Returns the longest common prefix of `a` and `b`.
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let end = a.char_indices()
+        .zip(b.chars())
+        .find(|&((_, a), b)| a != b)
+        .map_or_else(|| a.len().min(b.len()), |((idx, _), _)| idx);
+    &a[..end]
+}
+
+/// Infers the margin prefix as the longest common prefix token of the non-blank `lines`.
+///
+/// Returns `None` if there are no non-blank lines, or an `ErrorKind::AmbiguousMargin` error
+/// for the first line which does not share a prefix token with the preceding lines.
+pub(crate) fn detect_margin<'a>(lines: RawLines<'a>) -> Result<Option<&'a str>, MarginError> {
+    let mut margin: Option<&'a str> = None;
+    for line in lines.filter(|line| !is_blank(line.text)) {
+        let content = line.text.trim_start();
+        let token = prefix_token(content);
+        let common = margin.map_or(token, |margin| common_prefix(margin, token));
+        if common.is_empty() {
+            let column = line.text.len() - content.len();
+            return Err(MarginError::new(ErrorKind::AmbiguousMargin, line.number, line.offset + column, column,
+                                        line.text, margin.unwrap_or("")));
+        }
+        margin = Some(common);
+    }
+    Ok(margin)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use galvanic_assert::matchers::*;
+    use options::Policy;
+    use super::*;
+    use MarginTrimmable;
+
+    fn detect(input: &str) -> Result<Option<&str>, MarginError> {
+        detect_margin(RawLines::new(input, &Policy::default()).unwrap())
+    }
+
+    #[test]
+    fn should_detect_common_prefix_token() {
+        assert_that!(&detect("\n  |a\n\n  |b\n"), eq(Ok(Some("|"))));
+        assert_that!(&detect("\n  //! a\n  /// b\n"), eq(Ok(Some("//"))));
+        assert_that!(&detect("\n  >>a\n  >>>b\n"), eq(Ok(Some(">>"))));
+        assert_that!(&detect("\n  │a\n  │b\n"), eq(Ok(Some("│"))));
+    }
+
+    #[test]
+    fn should_trim_detected_prefix_despite_blank_lines() {
+        let txt = "\n  |a\n\n  |b\n";
+        assert_that!(&detect(txt), eq(Ok(Some("|"))));
+        assert_that!(&txt.try_trim_margin_auto(), eq(Ok("a\n\nb".to_string())));
+    }
+
+    #[test]
+    fn should_not_detect_prefix_without_non_blank_lines() {
+        assert_that!(&detect("\n  \n\t\n"), eq(Ok(None)));
+    }
+
+    #[test]
+    fn should_report_line_without_common_prefix() {
+        let error = detect("\n  |a\n  #b\n").unwrap_err();
+        assert_that!(&error.kind(), eq(ErrorKind::AmbiguousMargin));
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.prefix(), eq("|"));
+
+        let error = detect("\n  a\n  |b\n").unwrap_err();
+        assert_that!(&error.line(), eq(2));
+        assert_that!(&error.prefix(), eq(""));
+    }
+}
@@ -0,0 +1,254 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::string::{String, ToString};
+use core::fmt;
+use lines::RawLine;
+use pattern::Margin;
+
+
+/// The reason why a multi-line string could not be trimmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A line does not start with the expected margin prefix.
+    MissingPrefix,
+    /// No margin prefix could be detected which is shared by all lines.
+    AmbiguousMargin,
+    /// The last line ends with a continuation marker.
+    DanglingContinuation,
+    /// A line does not contain the expected right delimiter of the margin.
+    MissingRightDelimiter,
+    /// Reading a line from the input failed, e.g., in an asynchronous stream of trimmed lines.
+    ///
+    /// The `MarginError` contains the description and, with the `std` feature, the kind of the I/O error
+    /// instead of the offending line.
+    Io,
+}
+
+
+/// The error returned if the margin of a multi-line string could not be trimmed, e.g.,
+/// because a line does not start with the expected margin prefix.
+///
+/// Its `Display` implementation shows the offending line with a caret pointing at the error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarginError {
+    kind: ErrorKind,
+    line: usize,
+    offset: usize,
+    column: usize,
+    text: String,
+    prefix: String,
+    #[cfg(feature = "std")]
+    io_kind: Option<std::io::ErrorKind>,
+}
+
+impl MarginError {
+    pub(crate) fn new(kind: ErrorKind, line: usize, offset: usize, column: usize, text: &str, prefix: &str) -> MarginError {
+        MarginError {
+            kind, line, offset, column, text: text.into(), prefix: prefix.into(),
+            #[cfg(feature = "std")]
+            io_kind: None,
+        }
+    }
+
+    /// Creates an `ErrorKind::Io` error for the line which could not be read.
+    #[cfg(feature = "std")]
+    pub(crate) fn io(line: usize, offset: usize, error: &std::io::Error, prefix: &str) -> MarginError {
+        let description = error.to_string();
+        MarginError { io_kind: Some(error.kind()), ..MarginError::new(ErrorKind::Io, line, offset, 0, &description, prefix) }
+    }
+
+    /// The reason of the error.
+    pub fn kind(&self) -> ErrorKind { self.kind }
+
+    /// The 1-based number of the offending line.
+    pub fn line(&self) -> usize { self.line }
+
+    /// The byte offset in the original input at which the margin prefix was expected.
+    pub fn offset(&self) -> usize { self.offset }
+
+    /// The byte offset in the offending line at which the margin prefix was expected.
+    pub fn column(&self) -> usize { self.column }
+
+    /// The offending line as it appears in the original input.
+    ///
+    /// For an `ErrorKind::Io` there is no such line, so this is the description of the I/O error instead.
+    pub fn text(&self) -> &str { &self.text }
+
+    /// The kind of the I/O error for an `ErrorKind::Io`, `None` for all other kinds.
+    #[cfg(feature = "std")]
+    pub fn io_error_kind(&self) -> Option<std::io::ErrorKind> { self.io_kind }
+
+    /// The margin prefix which was expected.
+    ///
+    /// For an `ErrorKind::AmbiguousMargin` this is the prefix shared by the preceding lines, if any,
+    /// for an `ErrorKind::DanglingContinuation` the continuation marker
+    /// and for an `ErrorKind::MissingRightDelimiter` the right delimiter.
+    pub fn prefix(&self) -> &str { &self.prefix }
+
+    /// Describes the error without its location, e.g., for prefixing it with a file name and line number.
+    pub fn message(&self) -> String {
+        match self.kind {
+            ErrorKind::MissingPrefix => format!("expected margin prefix {:?}", self.prefix),
+            ErrorKind::AmbiguousMargin if self.prefix.is_empty() => "no margin prefix found".to_string(),
+            ErrorKind::AmbiguousMargin => format!("no margin prefix in common with {:?}", self.prefix),
+            ErrorKind::DanglingContinuation => format!("continuation marker {:?} without next line", self.prefix),
+            ErrorKind::MissingRightDelimiter => format!("expected right delimiter {:?}", self.prefix),
+            ErrorKind::Io => format!("reading the line failed: {}", self.text),
+        }
+    }
+
+    /// The blanks to put in front of the caret so that it points at the column of the error.
+    fn indentation(&self) -> String {
+        self.text.get(..self.column).unwrap_or(&self.text).chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect()
+    }
+}
+
+impl fmt::Display for MarginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.kind == ErrorKind::Io {
+            return write!(f, "line {}: {}", self.line, self.message());
+        }
+        writeln!(f, "line {}: {}", self.line, self.message())?;
+        writeln!(f, "{}", self.text)?;
+        write!(f, "{}^", self.indentation())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MarginError {}
+
+
+/// A line which could not be trimmed, i.e., a `MarginError` which has not been allocated yet.
+#[derive(Debug, Clone)]
+pub(crate) struct Fault<'a, 'p> {
+    pub kind: ErrorKind,
+    pub line: RawLine<'a>,
+    pub column: usize,
+    pub prefix: Margin<'p>,
+}
+
+impl<'a, 'p> From<Fault<'a, 'p>> for MarginError {
+    fn from(fault: Fault<'a, 'p>) -> MarginError {
+        let Fault { kind, line, column, prefix } = fault;
+        MarginError::new(kind, line.number, line.offset + column, column, line.text, &prefix.description())
+    }
+}
+
+
+/// The error returned by the non-allocating `MarginTrimmable::trim_margin_into` and `trim_margin_into_slice`.
+///
+/// Unlike `MarginError` it does not contain the offending line, so creating it does not allocate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TrimIntoError {
+    /// A line could not be trimmed.
+    Margin {
+        /// The reason of the error.
+        kind: ErrorKind,
+        /// The 1-based number of the offending line.
+        line: usize,
+        /// The byte offset in the original input at which the error was detected.
+        offset: usize,
+    },
+    /// The trimmed string does not fit into the buffer.
+    BufferTooSmall,
+    /// The writer reported an error.
+    Write,
+}
+
+impl<'a, 'p> From<Fault<'a, 'p>> for TrimIntoError {
+    fn from(fault: Fault<'a, 'p>) -> TrimIntoError {
+        TrimIntoError::Margin { kind: fault.kind, line: fault.line.number, offset: fault.line.offset + fault.column }
+    }
+}
+
+impl fmt::Display for TrimIntoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrimIntoError::Margin { kind, line, .. } => {
+                let message = match kind {
+                    ErrorKind::MissingPrefix => "missing margin prefix",
+                    ErrorKind::AmbiguousMargin => "no margin prefix in common with the preceding lines",
+                    ErrorKind::DanglingContinuation => "continuation marker without next line",
+                    ErrorKind::MissingRightDelimiter => "missing right delimiter",
+                    ErrorKind::Io => "reading the line failed",
+                };
+                write!(f, "line {}: {}", line, message)
+            },
+            TrimIntoError::BufferTooSmall => f.write_str("buffer too small for the trimmed string"),
+            TrimIntoError::Write => f.write_str("writing the trimmed string failed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TrimIntoError {}
+
+
+#[cfg(test)]
+mod tests {
+    use galvanic_assert::matchers::*;
+    use super::*;
+
+    #[test]
+    fn should_point_caret_at_expected_prefix_position() {
+        let error = MarginError::new(ErrorKind::MissingPrefix, 3, 17, 6, "      oops", "|");
+        assert_that!(&error.to_string(),
+                     eq(["line 3: expected margin prefix \"|\"", "      oops", "      ^"].join("\n")));
+    }
+
+    #[test]
+    fn should_keep_tabs_in_front_of_caret() {
+        let error = MarginError::new(ErrorKind::MissingPrefix, 1, 2, 2, "\t\toops", "#");
+        assert_that!(&error.to_string(),
+                     eq(["line 1: expected margin prefix \"#\"", "\t\toops", "\t\t^"].join("\n")));
+    }
+
+    #[test]
+    fn should_describe_ambiguous_margin() {
+        let error = MarginError::new(ErrorKind::AmbiguousMargin, 4, 30, 4, "    #oops", "|");
+        assert_that!(&error.to_string(),
+                     eq(["line 4: no margin prefix in common with \"|\"", "    #oops", "    ^"].join("\n")));
+    }
+
+    #[test]
+    fn should_point_caret_at_continuation_marker() {
+        let error = MarginError::new(ErrorKind::DanglingContinuation, 2, 13, 8, "  \t|abc \\", "\\");
+        assert_that!(&error.to_string(),
+                     eq(["line 2: continuation marker \"\\\\\" without next line", "  \t|abc \\", "  \t     ^"].join("\n")));
+    }
+
+    #[test]
+    fn should_describe_error_without_offending_line() {
+        let error = TrimIntoError::Margin { kind: ErrorKind::MissingPrefix, line: 3, offset: 17 };
+        assert_that!(&error.to_string(), eq("line 3: missing margin prefix".to_string()));
+        assert_that!(&TrimIntoError::BufferTooSmall.to_string(), eq("buffer too small for the trimmed string".to_string()));
+    }
+
+    #[test]
+    fn should_point_caret_behind_line_without_right_delimiter() {
+        let error = MarginError::new(ErrorKind::MissingRightDelimiter, 2, 16, 6, "  |ab ", "|");
+        assert_that!(&error.to_string(),
+                     eq(["line 2: expected right delimiter \"|\"", "  |ab ", "      ^"].join("\n")));
+    }
+
+    #[test]
+    fn should_describe_io_error_without_caret() {
+        let error = MarginError::new(ErrorKind::Io, 4, 40, 0, "connection reset", "|");
+        assert_that!(&error.to_string(), eq("line 4: reading the line failed: connection reset".to_string()));
+    }
+}
@@ -0,0 +1,240 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")] use std::io;
+
+
+/// A writer which inserts the indentation prefixes at the start of every line written through it,
+/// i.e., the writer-side counterpart of `MarginTrimmable::trim_margin_with`.
+///
+/// It implements `fmt::Write` and, with the `std` feature, `io::Write` if the inner writer does.
+/// The start of a line is tracked across writes, so lines may be written in several parts.
+/// Empty lines get the prefixes as well, so the output can be trimmed again,
+/// but nothing is inserted after a final line break.
+///
+/// ```
+/// extern crate trim_margin;
+/// use std::fmt::Write;
+/// use trim_margin::IndentWriter;
+///
+/// fn main() {
+///     let mut writer = IndentWriter::new(String::new(), "  |");
+///     writeln!(writer, "fn main() {{").unwrap();
+///     writer.push_indent("    ");
+///     write!(writer, "println!(").unwrap();
+///     writeln!(writer, "\"hello\");").unwrap();
+///     writer.pop_indent();
+///     write!(writer, "}}").unwrap();
+///     assert_eq!(writer.into_inner(), "  |fn main() {\n  |    println!(\"hello\");\n  |}");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndentWriter<W> {
+    inner: W,
+    indents: Vec<String>,
+    at_line_start: bool,
+}
+
+impl<W> IndentWriter<W> {
+    /// Creates a writer which inserts `prefix` at the start of every line written to `inner`.
+    pub fn new<M: AsRef<str>>(inner: W, prefix: M) -> IndentWriter<W> {
+        IndentWriter { inner, indents: vec![prefix.as_ref().into()], at_line_start: true }
+    }
+
+    /// Adds an indentation level, i.e., `prefix` is inserted after the prefixes of the previous levels.
+    pub fn push_indent<M: AsRef<str>>(&mut self, prefix: M) {
+        self.indents.push(prefix.as_ref().into());
+    }
+
+    /// Removes the innermost indentation level and returns its prefix.
+    pub fn pop_indent(&mut self) -> Option<String> {
+        self.indents.pop()
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W { &self.inner }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W { self.inner }
+}
+
+impl<W: fmt::Write> fmt::Write for IndentWriter<W> {
+    fn write_str(&mut self, mut s: &str) -> fmt::Result {
+        while !s.is_empty() {
+            if self.at_line_start {
+                for indent in &self.indents {
+                    self.inner.write_str(indent)?;
+                }
+            }
+            let end = s.find('\n').map_or(s.len(), |idx| idx + 1);
+            self.inner.write_str(&s[..end])?;
+            self.at_line_start = s[..end].ends_with('\n');
+            s = &s[end..];
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> IndentWriter<W> {
+    /// Writes `line`, which contains at most one line break at its end, preceded by the prefixes at a line start.
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.at_line_start {
+            for indent in &self.indents {
+                self.inner.write_all(indent.as_bytes())?;
+            }
+        }
+        self.inner.write_all(line)?;
+        self.at_line_start = line.ends_with(b"\n");
+        Ok(())
+    }
+}
+
+/// Writes whole lines, so if the inner writer fails after some lines the number of their bytes is returned
+/// and the error is only reported if no line could be written.
+#[cfg(feature = "std")]
+impl<W: io::Write> io::Write for IndentWriter<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let mut consumed = 0;
+        while consumed < buffer.len() {
+            let rest = &buffer[consumed..];
+            let end = rest.iter().position(|&b| b == b'\n').map_or(rest.len(), |idx| idx + 1);
+            match self.write_line(&rest[..end]) {
+                Ok(()) => consumed += end,
+                Err(_) if consumed > 0 => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(consumed)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+
+/// Displays a value with a prefix inserted at the start of every line, created by `indented`.
+#[derive(Debug, Clone, Copy)]
+pub struct Indented<'a, T: ?Sized + 'a> {
+    value: &'a T,
+    prefix: &'a str,
+}
+
+/// Wraps `value` so that its `Display` output has `prefix` inserted at the start of every line.
+///
+/// ```
+/// extern crate trim_margin;
+/// use trim_margin::indented;
+///
+/// fn main() {
+///     let list = "- first\n- second";
+///     assert_eq!(format!("items:\n{}", indented(&list, "  ")), "items:\n  - first\n  - second");
+/// }
+/// ```
+pub fn indented<'a, T: ?Sized + fmt::Display>(value: &'a T, prefix: &'a str) -> Indented<'a, T> {
+    Indented { value, prefix }
+}
+
+impl<'a, T: ?Sized + fmt::Display> fmt::Display for Indented<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use core::fmt::Write;
+        write!(IndentWriter::new(f, self.prefix), "{}", self.value)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use core::fmt::Write;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+    use super::*;
+    use MarginTrimmable;
+
+    #[test]
+    fn should_track_line_starts_across_writes() {
+        let mut writer = IndentWriter::new(String::new(), "> ");
+        write!(writer, "a").unwrap();
+        write!(writer, "b\n\nc\n").unwrap();
+        write!(writer, "d").unwrap();
+        assert_that!(&writer.into_inner(), eq("> ab\n> \n> c\n> d".to_string()));
+    }
+
+    #[test]
+    fn should_push_and_pop_indentation_levels() {
+        let mut writer = IndentWriter::new(String::new(), "");
+        writeln!(writer, "a").unwrap();
+        writer.push_indent("  ");
+        writer.push_indent("  ");
+        writeln!(writer, "b").unwrap();
+        assert_that!(&writer.pop_indent(), eq(Some("  ".to_string())));
+        writeln!(writer, "c").unwrap();
+        assert_that!(&writer.get_ref().as_str(), eq("a\n    b\n  c\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_write_bytes_with_indentation() {
+        use std::io::Write;
+        let mut writer = IndentWriter::new(Vec::new(), "  |");
+        writer.write_all(b"first\nsec").unwrap();
+        writer.write_all(b"ond\r\n").unwrap();
+        assert_that!(&writer.into_inner(), eq(b"  |first\n  |second\r\n".to_vec()));
+    }
+
+    /// A writer which fails if a write does not fit into its remaining `capacity`.
+    #[cfg(feature = "std")]
+    struct LimitedWriter {
+        written: Vec<u8>,
+        capacity: usize,
+    }
+
+    #[cfg(feature = "std")]
+    impl io::Write for LimitedWriter {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            if self.written.len() + buffer.len() > self.capacity {
+                return Err(io::Error::new(io::ErrorKind::WriteZero, "full"));
+            }
+            self.written.extend_from_slice(buffer);
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_report_written_lines_before_error() {
+        use std::io::Write;
+        let mut writer = IndentWriter::new(LimitedWriter { written: Vec::new(), capacity: 9 }, "  |");
+        assert_that!(&writer.write(b"first\nsecond\n").ok(), eq(Some(6)));
+        assert_that!(&writer.write(b"second\n").map_err(|error| error.kind()), eq(Err(io::ErrorKind::WriteZero)));
+        writer.inner.capacity = 64;
+        writer.write_all(b"second\n").unwrap();
+        assert_that!(&writer.into_inner().written, eq(b"  |first\n  |second\n".to_vec()));
+    }
+
+    #[test]
+    fn should_indent_display_output_which_is_trimmed_again() {
+        let txt = "first\n  second\n\nthird";
+        let block = format!("\n{}\n", indented(txt, "        |"));
+        assert_that!(&block.trim_margin(), maybe_some(eq(txt.to_string())));
+    }
+}
@@ -61,8 +61,72 @@
 //!     println!("{}", multiline_string_with_margin);
 //! }
 //! ```
+//!
+//! If you need to know why a string could not be trimmed use the `try_*` variants.
+//! They report the offending line in a `MarginError`.
+//!
+//! ```
+//! extern crate trim_margin;
+//! use trim_margin::MarginTrimmable;
+//!
+//! fn main() {
+//!     let error = "
+//!         |This line is fine,
+//!         but this one lacks the margin.
+//!     ".try_trim_margin().unwrap_err();
+//!     assert_eq!(error.line(), 3);
+//!     println!("{}", error);
+//! }
+//! ```
+
+#![no_std]
+#![allow(clippy::needless_doctest_main)]
 
+#[macro_use] extern crate alloc;
+#[cfg(any(feature = "std", test))] extern crate std;
+#[cfg(feature = "tokio")] extern crate futures_core;
+#[cfg(feature = "regex")] extern crate regex;
+#[cfg(feature = "tokio")] extern crate tokio;
 #[cfg(test)] #[macro_use] extern crate galvanic_assert;
+#[cfg(test)] extern crate proptest;
+
+mod add;
+#[cfg(feature = "tokio")] mod async_reader;
+mod comment;
+mod detect;
+mod error;
+mod indent;
+mod line_ending;
+mod lines;
+mod options;
+mod pattern;
+mod quote;
+#[cfg(feature = "std")] mod reader;
+#[cfg(feature = "regex")] mod regex_margin;
+mod scala;
+mod slice;
+
+#[cfg(feature = "tokio")] pub use async_reader::{AsyncMarginLines, AsyncMarginReader};
+pub use comment::CommentStyle;
+pub use error::{ErrorKind, MarginError, TrimIntoError};
+pub use indent::{indented, IndentWriter, Indented};
+pub use line_ending::{LineBreaks, LineEnding};
+pub use lines::MarginLines;
+pub use options::{Chomping, ContinuationJoin, Indentation, TrimOptions};
+pub use pattern::MarginPattern;
+#[cfg(feature = "std")] pub use reader::MarginReader;
+#[cfg(feature = "regex")] pub use regex_margin::{CapturedLine, MarginCaptures};
+
+use lines::{is_blank, strip_margin, RawLines};
+use options::Policy;
+use pattern::Margin;
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "regex")] use regex::Regex;
+use slice::SliceWriter;
 
 
 /// An interface for removing the margin of multi-line string-like objects.
@@ -71,50 +135,402 @@ pub trait MarginTrimmable {
     ///
     /// If the first or last line is blank (contains only whitespace, tabs, etc.) they are removed.
     /// From each remaining line leading blank characters and the subsequent are removed
+    /// Lines are terminated by `\n` or `\r\n` and keep their line break in the result.
+    /// The `margin_prefix` is a `MarginPattern`, e.g., a string, a `char` or a set of `char`s.
+    ///
+    /// # Returns
+    /// * The trimmed string or a `MarginError` describing the first line which does not start with a `margin_prefix`.
+    /// * Strings without line break unmodified
+    fn try_trim_margin_with<M: MarginPattern>(&self, margin_prefix: M) -> Result<String, MarginError>;
+
+    /// Removes blanks and the `margin_prefix` from multiline strings with configurable line breaks.
+    ///
+    /// Behaves like `try_trim_margin_with` but splits the lines at the given `line_breaks`
+    /// and terminates the lines of the result according to `line_ending`.
+    fn try_trim_margin_with_line_endings<M: MarginPattern>(&self, margin_prefix: M,
+                                                           line_breaks: LineBreaks,
+                                                           line_ending: LineEnding) -> Result<String, MarginError>;
+
+    /// Removes blanks and the `margin_prefix` from multiline strings and treats the end of the result as given by `chomping`.
+    ///
+    /// Behaves like `try_trim_margin_with` but, e.g., `Chomping::Clip` terminates the result by exactly one line break
+    /// as expected from the content of text files.
+    fn try_trim_margin_chomped<M: MarginPattern>(&self, margin_prefix: M, chomping: Chomping) -> Result<String, MarginError>;
+
+    /// Behaves like `try_trim_margin_chomped` but discards the error.
+    fn trim_margin_chomped<M: MarginPattern>(&self, margin_prefix: M, chomping: Chomping) -> Option<String> {
+        self.try_trim_margin_chomped(margin_prefix, chomping).ok()
+    }
+
+    /// Writes the string with the `margin_prefix` removed to `out` without allocating.
+    ///
+    /// The same rules as in `try_trim_margin_with` apply, so the same string is written as returned by it.
+    /// If an error occurs the lines in front of the offending line have already been written.
+    fn trim_margin_into<M: MarginPattern, W: fmt::Write>(&self, margin_prefix: M, out: &mut W) -> Result<(), TrimIntoError>;
+
+    /// Writes the string with the `margin_prefix` removed to `buffer` without allocating.
+    ///
+    /// # Returns
+    /// * The number of bytes written, i.e., `buffer[..len]` contains the trimmed string as UTF-8
+    /// * `TrimIntoError::BufferTooSmall` if the trimmed string does not fit into `buffer`
+    /// * `TrimIntoError::Margin` for the first line which does not start with a `margin_prefix`
+    fn trim_margin_into_slice<M: MarginPattern>(&self, margin_prefix: M, buffer: &mut [u8]) -> Result<usize, TrimIntoError> {
+        let mut writer = SliceWriter::new(buffer);
+        match self.trim_margin_into(margin_prefix, &mut writer) {
+            Ok(()) => Ok(writer.len()),
+            Err(TrimIntoError::Write) => Err(TrimIntoError::BufferTooSmall),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Short-hand for `try_trim_margin_with("|")`.
+    fn try_trim_margin(&self) -> Result<String, MarginError> { self.try_trim_margin_with("|") }
+
+    /// Removes the margin from multiline strings as configured by the `options`.
+    ///
+    /// # Returns
+    /// * The trimmed string or a `MarginError` describing the first line which does not start with the margin prefix.
+    /// * Strings without line break unmodified
+    fn try_trim_margin_opts(&self, options: &TrimOptions) -> Result<String, MarginError>;
+
+    /// Removes the margin from multiline strings as configured by the `options`.
+    ///
+    /// Behaves like `try_trim_margin_opts` but discards the error.
+    fn trim_margin_opts(&self, options: &TrimOptions) -> Option<String> {
+        self.try_trim_margin_opts(options).ok()
+    }
+
+    /// Removes blanks and the `margin_prefix` from multiline strings.
+    ///
+    /// Behaves like `try_trim_margin_with` but discards the error.
     ///
     /// # Returns
     /// * The trimmed string or `None` if not every line starts with a `margin_prefix`.
     /// * Strings without line break unmodified
-    fn trim_margin_with<M: AsRef<str>>(&self, margin_prefix: M) -> Option<String>;
+    fn trim_margin_with<M: MarginPattern>(&self, margin_prefix: M) -> Option<String> {
+        self.try_trim_margin_with(margin_prefix).ok()
+    }
 
     /// Short-hand for `trin_margin_with("|")`.
     fn trim_margin(&self) -> Option<String> { self.trim_margin_with("|") }
+
+    /// Removes blanks and the `margin_prefix` from multiline strings but accepts blank lines without margin.
+    ///
+    /// Behaves like `try_trim_margin_with` except that lines which are completely blank
+    /// do not need to start with the `margin_prefix`; they become empty lines in the result.
+    /// Non-blank lines without `margin_prefix` are still reported as error.
+    fn try_trim_margin_lenient_with<M: MarginPattern>(&self, margin_prefix: M) -> Result<String, MarginError>;
+
+    /// Behaves like `try_trim_margin_lenient_with` but discards the error.
+    fn trim_margin_lenient_with<M: MarginPattern>(&self, margin_prefix: M) -> Option<String> {
+        self.try_trim_margin_lenient_with(margin_prefix).ok()
+    }
+
+    /// Short-hand for `trim_margin_lenient_with("|")`.
+    fn trim_margin_lenient(&self) -> Option<String> { self.trim_margin_lenient_with("|") }
+
+    /// Removes the margin from multiline strings and folds the lines into paragraphs like a YAML `>` block scalar.
+    ///
+    /// Consecutive non-empty lines inside the margin are joined by a single space.
+    /// Empty lines become paragraph breaks: `n` empty lines between two text lines result in `n` line breaks.
+    /// More-indented lines, i.e., lines whose content starts with a space or tab, are kept verbatim on their own line;
+    /// the line breaks around them are preserved.
+    /// Empty lines at the end become line breaks.
+    ///
+    /// # Returns
+    /// * The folded string or a `MarginError` describing the first line which does not start with a `margin_prefix`.
+    /// * Strings without line break unmodified
+    fn try_trim_margin_folded<M: MarginPattern>(&self, margin_prefix: M) -> Result<String, MarginError>;
+
+    /// Behaves like `try_trim_margin_folded` but discards the error.
+    fn trim_margin_folded<M: MarginPattern>(&self, margin_prefix: M) -> Option<String> {
+        self.try_trim_margin_folded(margin_prefix).ok()
+    }
+
+    /// Removes the margin as configured by the `options` and folds the lines like `try_trim_margin_folded`.
+    ///
+    /// The continuation options do not apply as the lines are joined by folding.
+    fn try_trim_margin_folded_opts(&self, options: &TrimOptions) -> Result<String, MarginError>;
+
+    /// Removes blanks and the `margin_prefix` from multiline strings without allocating if possible.
+    ///
+    /// Behaves like `trim_margin_with` but borrows from `self` if no new string has to be built.
+    /// This is the case for strings without line break and for strings with only a single line inside the margin.
+    fn trim_margin_with_cow<M: MarginPattern>(&self, margin_prefix: M) -> Option<Cow<'_, str>>;
+
+    /// Short-hand for `trim_margin_with_cow("|")`.
+    fn trim_margin_cow(&self) -> Option<Cow<'_, str>> { self.trim_margin_with_cow("|") }
+
+    /// Returns a lazy iterator over the lines with their `margin_prefix` removed.
+    ///
+    /// The same rules as in `try_trim_margin_with` apply, but the lines are yielded as slices of `self`
+    /// instead of being joined into a new string. The `margin_prefix` is a `MarginPattern` which is owned by the iterator.
+    fn margin_lines<'a, M: MarginPattern + 'a>(&'a self, margin_prefix: M) -> MarginLines<'a>;
+
+    /// Returns a lazy iterator over the lines with their margin removed as configured by the `options`.
+    ///
+    /// The continuation, line ending and trailing newline options do not apply as the lines are yielded
+    /// as they are without line break.
+    fn margin_lines_opts<'a>(&'a self, options: &'a TrimOptions) -> MarginLines<'a>;
+
+    /// Returns a lazy iterator over the lines with the margin matched by the `margin` regex removed.
+    ///
+    /// The `margin` has to match at the start of every line after its leading blanks, e.g., `(\d+)\| ` for line numbers.
+    /// Besides the content of a line the captures of its margin are yielded, so variable margins are not lost.
+    #[cfg(feature = "regex")]
+    fn margin_captures<'a>(&'a self, margin: &'a Regex) -> MarginCaptures<'a>;
+
+    /// Infers the margin prefix of a multiline string.
+    ///
+    /// The margin prefix is the longest run of non-blank, non-alphanumeric characters which starts every
+    /// non-blank line after its leading blanks. A blank first and last line are ignored like in `trim_margin_with`.
+    ///
+    /// # Returns
+    /// * The detected margin prefix
+    /// * `None` if the lines do not share such a prefix, if all lines are blank or for strings without line break
+    fn detect_margin(&self) -> Option<String>;
+
+    /// Removes blanks and the margin prefix inferred by `detect_margin` from multiline strings.
+    ///
+    /// As blank lines are ignored by the detection, they may omit the margin prefix like in `try_trim_margin_lenient_with`.
+    ///
+    /// # Returns
+    /// * The trimmed string or a `MarginError` of kind `ErrorKind::AmbiguousMargin` for the first line
+    ///   which does not share a margin prefix with the preceding lines.
+    /// * Strings without line break unmodified
+    fn try_trim_margin_auto(&self) -> Result<String, MarginError>;
+
+    /// Behaves like `try_trim_margin_auto` but discards the error.
+    fn trim_margin_auto(&self) -> Option<String> { self.try_trim_margin_auto().ok() }
+
+    /// Renders a string as a margin block, i.e., the inverse of `trim_margin_with`.
+    ///
+    /// Every line is indented by `indent` spaces followed by the `margin_prefix`.
+    /// The block starts and ends with a blank line, so `text.add_margin(i, p).trim_margin_with(p) == Some(text)`
+    /// holds for every `text` as long as the `margin_prefix` is not empty and does not start with a blank.
+    fn add_margin<M: AsRef<str>>(&self, indent: usize, margin_prefix: M) -> String {
+        self.add_margin_opts(indent, &TrimOptions::new().prefix(margin_prefix))
+    }
+
+    /// Renders a string as a margin block which is trimmed back by `trim_margin_opts(options)`.
+    ///
+    /// Uses the margin prefix, the right delimiter, the indentation, the line breaks and the line ending of the `options`
+    /// and surrounds the block with as many blank lines as are removed at the start and the end.
+    /// If the `options` terminate the last line, e.g., by `trailing_newline`, its line break is left to the trimming.
+    /// Some strings cannot be trimmed back:
+    /// * Strings without line break are not trimmed, so single line strings need at least one surrounding blank line
+    /// * If the `options` terminate the last line the string has to end with a line break,
+    ///   which is the one of the preceding line if no blank lines are removed at the end
+    /// * `Chomping::Strip` and `Chomping::Clip` remove blank lines at the end of the string
+    /// * Lines ending with the continuation marker are joined with the next line
+    fn add_margin_opts(&self, indent: usize, options: &TrimOptions) -> String;
+
+    /// Removes the comment leaders from a block of comment lines.
+    ///
+    /// From every line blanks, the comment leader of the `style` (e.g., `///` or `*`) and a single following space
+    /// are removed. The delimiters of a block comment, i.e., `/*`, `/**` or `/*!` on the first and `*/` on the last line,
+    /// are removed as well; text next to them is kept and lines consisting only of a delimiter are removed.
+    /// A blank first and last line are removed like in `trim_margin_with`, but unlike there
+    /// a string without line break is trimmed as a single comment line.
+    ///
+    /// # Returns
+    /// * The comment text or a `MarginError` describing the first line which does not start with the comment leader.
+    fn try_trim_comment(&self, style: CommentStyle) -> Result<String, MarginError>;
+
+    /// Behaves like `try_trim_comment` but discards the error.
+    fn trim_comment(&self, style: CommentStyle) -> Option<String> { self.try_trim_comment(style).ok() }
+
+    /// Returns the quote depth of every line, i.e., how often it starts with the `quote_prefix`.
+    ///
+    /// The quote levels may be preceded by spaces and tabs, e.g., `> > text` and `>> text` both have a depth of 2.
+    /// Unlike `trim_margin_with`, every line counts including blank first and last lines.
+    fn quote_depths<M: AsRef<str>>(&self, quote_prefix: M) -> Vec<usize>;
+
+    /// Removes `levels` quote levels from every line, e.g., `> > text` becomes `> text` for a single level.
+    ///
+    /// A single space following the last removed `quote_prefix` is removed as well.
+    /// Lines are terminated by `\n` or `\r\n` and keep their line break; blank first and last lines are kept.
+    ///
+    /// # Returns
+    /// * The string with the quote levels removed
+    /// * A `MarginError` describing the first line with less than `levels` quote levels
+    fn try_strip_quote_levels<M: AsRef<str>>(&self, levels: usize, quote_prefix: M) -> Result<String, MarginError>;
+
+    /// Behaves like `try_strip_quote_levels` but discards the error.
+    fn strip_quote_levels<M: AsRef<str>>(&self, levels: usize, quote_prefix: M) -> Option<String> {
+        self.try_strip_quote_levels(levels, quote_prefix).ok()
+    }
+
+    /// Adds a quote level to every line, i.e., the inverse of `strip_quote_levels(1, quote_prefix)`.
+    ///
+    /// Non-empty lines are separated from the `quote_prefix` by a space, so `text` becomes `> text`
+    /// and `> text` becomes `> > text`. The `quote_prefix` must not be empty for the round-trip.
+    fn add_quote_level<M: AsRef<str>>(&self, quote_prefix: M) -> String;
+
+    /// Removes the margin like Scala's `stripMargin`.
+    ///
+    /// From every line which starts with blanks (spaces and control characters) followed by the `margin_char`
+    /// the blanks and the `margin_char` are removed.
+    /// Unlike `trim_margin_with`, lines without margin are kept unmodified and blank first and last lines are not removed.
+    /// Lines are terminated by `\n`, `\r\n` or `\r` as in Scala 2.13 and keep their line break.
+    fn strip_margin_scala(&self, margin_char: char) -> String;
+
+    /// Removes the common indentation from multiline strings.
+    ///
+    /// If the first or last line is blank (contains only whitespace, tabs, etc.) they are removed.
+    /// From each remaining line the minimal number of leading blank characters across all non-blank lines is removed.
+    /// The relative indentation of the lines is preserved; blank lines shorter than the common indentation become empty.
+    ///
+    /// # Returns
+    /// * The dedented string
+    /// * Strings without line break unmodified
+    fn trim_indent(&self) -> String;
 }
 
 impl<S: AsRef<str>> MarginTrimmable for S {
-    fn trim_margin_with<M: AsRef<str>>(&self, margin_prefix: M) -> Option<String> {
-        let lines: Vec<_> = self.as_ref().split('\n').map(|line| line.trim_left()).collect();
-        if lines.len() <= 1 {
-            return Some(self.as_ref().into());
-        }
+    fn try_trim_margin_with<M: MarginPattern>(&self, margin_prefix: M) -> Result<String, MarginError> {
+        MarginLines::new(self.as_ref(), Margin::Pattern(&margin_prefix), &Policy::default()).into_string(None)
+    }
+
+    fn try_trim_margin_with_line_endings<M: MarginPattern>(&self, margin_prefix: M,
+                                                           line_breaks: LineBreaks,
+                                                           line_ending: LineEnding) -> Result<String, MarginError> {
+        let policy = Policy { line_breaks, line_ending, ..Policy::default() };
+        MarginLines::new(self.as_ref(), Margin::Pattern(&margin_prefix), &policy).into_string(None)
+    }
+
+    fn try_trim_margin_chomped<M: MarginPattern>(&self, margin_prefix: M, chomping: Chomping) -> Result<String, MarginError> {
+        let policy = Policy { chomping: Some(chomping), ..Policy::default() };
+        MarginLines::new(self.as_ref(), Margin::Pattern(&margin_prefix), &policy).into_string(None)
+    }
+
+    fn try_trim_margin_opts(&self, options: &TrimOptions) -> Result<String, MarginError> {
+        self.margin_lines_opts(options).into_string(options.continuation_marker())
+    }
+
+    fn try_trim_margin_lenient_with<M: MarginPattern>(&self, margin_prefix: M) -> Result<String, MarginError> {
+        let policy = Policy { blank_lines_without_prefix: true, ..Policy::default() };
+        MarginLines::new(self.as_ref(), Margin::Pattern(&margin_prefix), &policy).into_string(None)
+    }
+
+    fn try_trim_margin_folded<M: MarginPattern>(&self, margin_prefix: M) -> Result<String, MarginError> {
+        MarginLines::new(self.as_ref(), Margin::Pattern(&margin_prefix), &Policy::default()).into_folded()
+    }
+
+    fn try_trim_margin_folded_opts(&self, options: &TrimOptions) -> Result<String, MarginError> {
+        self.margin_lines_opts(options).into_folded()
+    }
+
+    fn trim_margin_into<M: MarginPattern, W: fmt::Write>(&self, margin_prefix: M, out: &mut W) -> Result<(), TrimIntoError> {
+        MarginLines::new(self.as_ref(), Margin::Pattern(&margin_prefix), &Policy::default())
+            .render(None, out)
+            .map_err(TrimIntoError::from)
+    }
+
+    fn trim_margin_with_cow<M: MarginPattern>(&self, margin_prefix: M) -> Option<Cow<'_, str>> {
+        let input = self.as_ref();
+        let policy = Policy::default();
+        let mut lines = match RawLines::new(input, &policy) {
+            Some(lines) => lines,
+            None => return Some(Cow::Borrowed(input)),
+        };
 
-        let mut with_margin: Vec<&str> = Vec::with_capacity(lines.len());
-        let mut line_iter = lines.into_iter().peekable();
-        if line_iter.peek().map_or(false, |l| l.is_empty()) {
-            line_iter.next();
+        let first = match lines.next() {
+            Some(line) => strip_margin(line, &Margin::Pattern(&margin_prefix), None, &policy).ok()?,
+            None => return Some(Cow::Borrowed("")),
+        };
+        if lines.next().is_none() {
+            return Some(Cow::Borrowed(first));
         }
+        self.trim_margin_with(margin_prefix).map(Cow::Owned)
+    }
 
-        let prefix = margin_prefix.as_ref();
-        while let Some(line) = line_iter.next() {
-            let is_last_line = line_iter.peek().is_none();
-            if is_last_line && line.is_empty() {
-                continue;
-            }
-            if !line.starts_with(prefix) {
-                return None;
-            }
-            with_margin.push(&line[prefix.len()..]);
+    fn margin_lines<'a, M: MarginPattern + 'a>(&'a self, margin_prefix: M) -> MarginLines<'a> {
+        MarginLines::new(self.as_ref(), Margin::Shared(Rc::new(margin_prefix)), &Policy::default())
+    }
+
+    fn margin_lines_opts<'a>(&'a self, options: &'a TrimOptions) -> MarginLines<'a> {
+        MarginLines::new(self.as_ref(), options.margin(), options.policy())
+            .with_right_delimiter(options.right_margin_delimiter())
+    }
+
+    #[cfg(feature = "regex")]
+    fn margin_captures<'a>(&'a self, margin: &'a Regex) -> MarginCaptures<'a> {
+        MarginCaptures::new(self.as_ref(), margin, &Policy::default())
+    }
+
+    fn detect_margin(&self) -> Option<String> {
+        let lines = RawLines::new(self.as_ref(), &Policy::default())?;
+        detect::detect_margin(lines).ok()?.map(String::from)
+    }
+
+    fn try_trim_margin_auto(&self) -> Result<String, MarginError> {
+        let input = self.as_ref();
+        let lines = match RawLines::new(input, &Policy::default()) {
+            Some(lines) => lines,
+            None => return Ok(input.into()),
+        };
+        let prefix = detect::detect_margin(lines)?.unwrap_or("");
+        self.try_trim_margin_lenient_with(prefix)
+    }
+
+    fn add_margin_opts(&self, indent: usize, options: &TrimOptions) -> String {
+        add::add_margin(self.as_ref(), indent, options.margin_prefix(), options.right_margin_delimiter(), options.policy())
+    }
+
+    fn try_trim_comment(&self, style: CommentStyle) -> Result<String, MarginError> {
+        comment::trim_comment(self.as_ref(), style, &Policy::default())
+    }
+
+    fn quote_depths<M: AsRef<str>>(&self, quote_prefix: M) -> Vec<usize> {
+        quote::quote_depths(self.as_ref(), quote_prefix.as_ref())
+    }
+
+    fn try_strip_quote_levels<M: AsRef<str>>(&self, levels: usize, quote_prefix: M) -> Result<String, MarginError> {
+        quote::strip_quote_levels(self.as_ref(), levels, quote_prefix.as_ref())
+    }
+
+    fn add_quote_level<M: AsRef<str>>(&self, quote_prefix: M) -> String {
+        quote::add_quote_level(self.as_ref(), quote_prefix.as_ref())
+    }
+
+    fn strip_margin_scala(&self, margin_char: char) -> String {
+        scala::strip_margin(self.as_ref(), margin_char)
+    }
+
+    fn trim_indent(&self) -> String {
+        let input = self.as_ref();
+        let lines = match RawLines::new(input, &Policy::default()) {
+            Some(lines) => lines,
+            None => return input.into(),
         };
 
-        Some(with_margin.join("\n"))
+        let indentation = lines.clone()
+            .filter(|line| !is_blank(line.text))
+            .map(|line| line.text.chars().take_while(|c| c.is_whitespace()).count())
+            .min()
+            .unwrap_or(0);
+
+        let mut dedented = String::with_capacity(input.len());
+        let mut terminator = "";
+        for line in lines {
+            dedented.push_str(terminator);
+            if let Some((idx, _)) = line.text.char_indices().nth(indentation) {
+                dedented.push_str(&line.text[idx..]);
+            }
+            terminator = line.ending;
+        }
+        dedented
     }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use alloc::string::ToString;
     use galvanic_assert::matchers::*;
     use galvanic_assert::matchers::variant::*;
+    use proptest::prelude::*;
     use super::*;
 
     #[test]
@@ -134,7 +550,7 @@ mod tests {
                    |  multiline string
                    |with margin";
         assert_that!(&txt.trim_margin(),
-                     maybe_some(eq(vec!["this", "  is a", "  multiline string", "with margin"].join("\n"))));
+                     maybe_some(eq(["this", "  is a", "  multiline string", "with margin"].join("\n"))));
     }
 
     #[test]
@@ -144,7 +560,7 @@ mod tests {
             |surrounding lines
         ";
         assert_that!(&txt.trim_margin(),
-                     maybe_some(eq(vec!["ignore blank", "surrounding lines"].join("\n"))));
+                     maybe_some(eq(["ignore blank", "surrounding lines"].join("\n"))));
     }
 
     #[test]
@@ -154,6 +570,421 @@ mod tests {
             #surrounding lines
         ";
         assert_that!(&txt.trim_margin_with("#"),
-                     maybe_some(eq(vec!["ignore blank", "surrounding lines"].join("\n"))));
+                     maybe_some(eq(["ignore blank", "surrounding lines"].join("\n"))));
+    }
+
+    #[test]
+    fn should_report_line_without_margin() {
+        let txt = "
+            |first line
+            second line
+        ";
+        let error = txt.try_trim_margin().unwrap_err();
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.offset(), eq(37));
+        assert_that!(&error.text(), eq("            second line"));
+        assert_that!(&error.prefix(), eq("|"));
+    }
+
+    #[test]
+    fn should_fail_on_missing_margin_in_option_variant() {
+        let txt = "
+            |first line
+            second line
+        ";
+        assert_that!(&txt.trim_margin(), eq(None));
+    }
+
+    #[test]
+    fn should_report_custom_margin_prefix() {
+        let txt = "
+            #first line
+            |second line
+        ";
+        let error = txt.try_trim_margin_with("#").unwrap_err();
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.prefix(), eq("#"));
+    }
+
+    #[test]
+    fn should_trim_margin_matched_by_pattern() {
+        let txt = "
+            │first line
+            ¦  second line
+        ";
+        assert_that!(&txt.trim_margin_with(['│', '¦']), maybe_some(eq("first line\n  second line".to_string())));
+        let error = txt.try_trim_margin_with('│').unwrap_err();
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.prefix(), eq("│"));
+    }
+
+    #[test]
+    fn should_combine_pattern_with_other_trimming_variants() {
+        let txt = "\n  │first\n\n  ¦ second\n";
+        let bars = ['│', '¦'];
+        assert_that!(&txt.trim_margin_lenient_with(bars), maybe_some(eq("first\n\n second".to_string())));
+        assert_that!(&txt.trim_margin_with(bars), eq(None));
+        let txt = "\n  │first\r\n  ¦ second\n";
+        assert_that!(&txt.trim_margin_folded(bars), maybe_some(eq("first\r\n second".to_string())));
+        assert_that!(&txt.trim_margin_chomped(bars, Chomping::Clip), maybe_some(eq("first\r\n second\n".to_string())));
+        assert_that!(&txt.try_trim_margin_with_line_endings(bars, LineBreaks::LfOrCrLf, LineEnding::Lf).ok(),
+                     maybe_some(eq("first\n second".to_string())));
+        let lines: Vec<_> = txt.margin_lines(bars).collect();
+        assert_that!(&lines, eq(vec![Ok("first"), Ok(" second")]));
+        let options = TrimOptions::new().prefix_pattern(bars).trailing_newline(true);
+        assert_that!(&txt.trim_margin_opts(&options), maybe_some(eq("first\r\n second\n".to_string())));
+        assert_that!(&options.clone(), eq(options.clone()));
+        assert_that!(&options.clone().prefix("|"), eq(TrimOptions::new().trailing_newline(true)));
+    }
+
+    #[test]
+    fn should_borrow_prefix_from_cow() {
+        let prefix: Cow<str> = Cow::Borrowed("|");
+        assert_that!(&"\n  |a\n".trim_margin_with(&prefix), maybe_some(eq("a".to_string())));
+        assert_that!(&"\n  |a\n".margin_lines(&prefix).collect::<Vec<_>>(), eq(vec![Ok("a")]));
+    }
+
+    #[test]
+    fn should_remove_common_indentation() {
+        let txt = "
+            fn main() {
+                println!(\"hello\");
+            }
+        ";
+        assert_that!(&txt.trim_indent(),
+                     eq(["fn main() {", "    println!(\"hello\");", "}"].join("\n")));
+    }
+
+    #[test]
+    fn should_ignore_blank_lines_for_common_indentation() {
+        let txt = "
+                first
+
+              \t
+                second
+        ";
+        assert_that!(&txt.trim_indent(), eq(["first", "", "", "second"].join("\n")));
+    }
+
+    #[test]
+    fn should_keep_whitespace_beyond_common_indentation_in_blank_lines() {
+        let txt = "
+            first
+                  
+            second
+        ";
+        assert_that!(&txt.trim_indent(), eq(["first", "      ", "second"].join("\n")));
+    }
+
+    #[test]
+    fn should_not_modify_single_line_string_when_trimming_indent() {
+        assert_that!(&"    hello, world".trim_indent(), eq("    hello, world".to_string()));
+    }
+
+    #[test]
+    fn should_iterate_over_lines_without_margin() {
+        let txt = "
+            |first
+            |  second
+        ";
+        let mut lines = txt.margin_lines("|");
+        assert_that!(&lines.next(), maybe_some(eq(Ok("first"))));
+        assert_that!(&lines.next(), maybe_some(eq(Ok("  second"))));
+        assert_that!(&lines.next(), eq(None));
+    }
+
+    #[test]
+    fn should_borrow_single_line_string() {
+        let txt = "hello, world";
+        assert_that!(&txt.trim_margin_cow(), maybe_some(eq(Cow::Borrowed("hello, world"))));
+        assert_that!(&matches!(txt.trim_margin_cow(), Some(Cow::Borrowed(_))), eq(true));
+    }
+
+    #[test]
+    fn should_borrow_single_margin_line() {
+        let txt = "
+            |only line
+        ";
+        assert_that!(&matches!(txt.trim_margin_cow(), Some(Cow::Borrowed("only line"))), eq(true));
+    }
+
+    #[test]
+    fn should_allocate_for_multiple_margin_lines() {
+        let txt = "
+            #first
+            #second
+        ";
+        let trimmed = txt.trim_margin_with_cow("#");
+        assert_that!(&matches!(trimmed, Some(Cow::Owned(_))), eq(true));
+        assert_that!(&trimmed.unwrap().into_owned(), eq(txt.trim_margin_with("#").unwrap()));
+    }
+
+    #[test]
+    fn should_fail_on_missing_margin_in_cow_variant() {
+        let txt = "
+            |first
+            second
+        ";
+        assert_that!(&txt.trim_margin_cow(), eq(None));
+    }
+
+    #[test]
+    fn should_preserve_crlf_line_breaks() {
+        let txt = "\r\n    |first\r\n    |second\r\n  ";
+        assert_that!(&txt.trim_margin(), maybe_some(eq("first\r\nsecond".to_string())));
+    }
+
+    #[test]
+    fn should_normalize_line_endings_if_requested() {
+        let txt = "\r\n    |first\r\n    |second\n    |third\r\n  ";
+        assert_that!(&txt.try_trim_margin_with_line_endings("|", LineBreaks::LfOrCrLf, LineEnding::Lf),
+                     maybe_ok(eq("first\nsecond\nthird".to_string())));
+        assert_that!(&txt.try_trim_margin_with_line_endings("|", LineBreaks::LfOrCrLf, LineEnding::CrLf),
+                     maybe_ok(eq("first\r\nsecond\r\nthird".to_string())));
+    }
+
+    #[test]
+    fn should_split_at_lone_cr_if_requested() {
+        let txt = "\r    |first\r    |second\r  ";
+        assert_that!(&txt.try_trim_margin_with_line_endings("|", LineBreaks::Any, LineEnding::Lf),
+                     maybe_ok(eq("first\nsecond".to_string())));
+        assert_that!(&txt.try_trim_margin_with_line_endings("|", LineBreaks::LfOrCrLf, LineEnding::Lf),
+                     maybe_ok(eq(txt.to_string())));
+    }
+
+    #[test]
+    fn should_trim_margin_into_buffer() {
+        let txt = "
+            |first
+            |  second
+        ";
+        let mut buffer = [0u8; 16];
+        let len = txt.trim_margin_into_slice("|", &mut buffer).unwrap();
+        assert_that!(&std::str::from_utf8(&buffer[..len]), maybe_ok(eq("first\n  second")));
+        assert_that!(&txt.trim_margin_into_slice("|", &mut buffer[..10]), maybe_err(eq(TrimIntoError::BufferTooSmall)));
+
+        let mut trimmed = String::new();
+        assert_that!(&txt.trim_margin_into("|", &mut trimmed), maybe_ok(eq(())));
+        assert_that!(&trimmed, eq("first\n  second".to_string()));
+    }
+
+    #[test]
+    fn should_report_line_without_margin_when_trimming_into_buffer() {
+        let txt = "
+            |first line
+            second line
+        ";
+        let mut buffer = [0u8; 64];
+        assert_that!(&txt.trim_margin_into_slice("|", &mut buffer),
+                     maybe_err(eq(TrimIntoError::Margin { kind: ErrorKind::MissingPrefix, line: 3, offset: 37 })));
+    }
+
+    proptest! {
+        #[test]
+        fn should_trim_into_buffer_like_trim_margin_with(text in "[ab |\t\r\n]{0,40}") {
+            let mut buffer = [0u8; 64];
+            let written = text.trim_margin_into_slice("|", &mut buffer);
+            match text.try_trim_margin_with("|") {
+                Ok(trimmed) => prop_assert_eq!(written.map(|len| &buffer[..len]), Ok(trimmed.as_bytes())),
+                Err(error) => prop_assert_eq!(written, Err(TrimIntoError::Margin {
+                    kind: error.kind(), line: error.line(), offset: error.offset()
+                })),
+            }
+        }
+    }
+
+    #[test]
+    fn should_trim_margin_with_default_options_like_trim_margin() {
+        let txt = "
+            |first
+            |  second
+        ";
+        assert_that!(&txt.trim_margin_opts(&TrimOptions::default()), eq(txt.trim_margin()));
+    }
+
+    #[test]
+    fn should_trim_margin_with_custom_options() {
+        let txt = "
+
+            #first
+        \t
+            #second
+
+        ";
+        let options = TrimOptions::new()
+            .prefix("#")
+            .leading_blank_lines(2)
+            .trailing_blank_lines(2)
+            .blank_lines_without_prefix(true)
+            .trailing_newline(true);
+        assert_that!(&txt.try_trim_margin_opts(&options), maybe_ok(eq("first\n\nsecond\n".to_string())));
+    }
+
+    #[test]
+    fn should_only_accept_configured_indentation() {
+        let txt = "
+            |spaces
+        \t|tab
+        ";
+        let options = TrimOptions::new().indentation(Indentation::Spaces);
+        let error = txt.try_trim_margin_opts(&options).unwrap_err();
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.column(), eq(8));
+    }
+
+    #[test]
+    fn should_append_trailing_newline_as_configured_line_ending() {
+        let txt = "\r\n  |first\r\n  |second";
+        let options = TrimOptions::new().trailing_newline(true);
+        assert_that!(&txt.try_trim_margin_opts(&options), maybe_ok(eq("first\r\nsecond\r\n".to_string())));
+        let options = options.line_ending(LineEnding::Lf);
+        assert_that!(&txt.try_trim_margin_opts(&options), maybe_ok(eq("first\nsecond\n".to_string())));
+    }
+
+    #[test]
+    fn should_accept_blank_lines_without_margin_in_lenient_mode() {
+        let txt = "
+            |first paragraph
+
+            |second paragraph
+        \t
+            |third paragraph
+        ";
+        assert_that!(&txt.trim_margin(), eq(None));
+        assert_that!(&txt.trim_margin_lenient(),
+                     maybe_some(eq(["first paragraph", "", "second paragraph", "", "third paragraph"].join("\n"))));
+    }
+
+    #[test]
+    fn should_reject_non_blank_lines_without_margin_in_lenient_mode() {
+        let txt = "
+            #first
+
+            second
+        ";
+        let error = txt.try_trim_margin_lenient_with("#").unwrap_err();
+        assert_that!(&error.line(), eq(4));
+    }
+
+    #[test]
+    fn should_add_margin_which_is_trimmed_again() {
+        let txt = "fn main() {\n    |println!(\"hello\");\n}";
+        let with_margin = txt.add_margin(8, "|");
+        assert_that!(&with_margin, eq("\n        |fn main() {\n        |    |println!(\"hello\");\n        |}\n".to_string()));
+        assert_that!(&with_margin.trim_margin(), maybe_some(eq(txt.to_string())));
+    }
+
+    #[test]
+    fn should_trim_detected_margin() {
+        let txt = "
+            >> first
+            >>   second
+        ";
+        assert_that!(&txt.detect_margin(), maybe_some(eq(">>".to_string())));
+        assert_that!(&txt.trim_margin_auto(), maybe_some(eq(" first\n   second".to_string())));
+    }
+
+    #[test]
+    fn should_report_ambiguous_margin() {
+        let txt = "
+            |first
+            #second
+        ";
+        assert_that!(&txt.detect_margin(), eq(None));
+        let error = txt.try_trim_margin_auto().unwrap_err();
+        assert_that!(&error.kind(), eq(ErrorKind::AmbiguousMargin));
+        assert_that!(&error.line(), eq(3));
+    }
+
+    #[test]
+    fn should_join_lines_with_continuation_marker() {
+        let txt = "
+            |error: the margin of line 3 \\
+            |       is missing
+            |hint: add a margin
+        ";
+        let options = TrimOptions::new().continuation("\\");
+        assert_that!(&txt.try_trim_margin_opts(&options),
+                     maybe_ok(eq("error: the margin of line 3 is missing\nhint: add a margin".to_string())));
+    }
+
+    #[test]
+    fn should_preserve_trailing_blanks_before_right_delimiter() {
+        let txt = "
+            |hard break  |
+            |next line|
+        ";
+        let options = TrimOptions::new().right_delimiter("|");
+        assert_that!(&txt.try_trim_margin_opts(&options), maybe_ok(eq("hard break  \nnext line".to_string())));
+        let error = "\n  |first|\n  |second\n".try_trim_margin_opts(&options).unwrap_err();
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.message(), eq("expected right delimiter \"|\"".to_string()));
+    }
+
+    #[test]
+    fn should_extract_text_of_doc_comment() {
+        let header = "
+            /**
+             * Copyright 2018
+             *
+             * Licensed under the Apache License.
+             */
+        ";
+        assert_that!(&header.trim_comment(CommentStyle::CBlock),
+                     maybe_some(eq("Copyright 2018\n\nLicensed under the Apache License.".to_string())));
+        assert_that!(&header.trim_comment(CommentStyle::Hash), eq(None));
+    }
+
+    #[test]
+    fn should_requote_reply() {
+        let reply = "> > Is it done?\n> Yes.\n\nGreat!";
+        assert_that!(&reply.quote_depths(">"), eq(vec![2, 1, 0, 0]));
+        let requoted = reply.add_quote_level(">");
+        assert_that!(&requoted, eq("> > > Is it done?\n> > Yes.\n>\n> Great!".to_string()));
+        assert_that!(&requoted.strip_quote_levels(1, ">"), maybe_some(eq(reply.to_string())));
+        assert_that!(&requoted.strip_quote_levels(2, ">"), eq(None));
+    }
+
+    #[test]
+    fn should_terminate_chomped_result_by_single_line_break() {
+        let txt = "
+            #echo hello
+            #
+        ";
+        assert_that!(&txt.trim_margin_with("#"), maybe_some(eq("echo hello\n".to_string())));
+        assert_that!(&txt.trim_margin_chomped("#", Chomping::Strip), maybe_some(eq("echo hello".to_string())));
+        assert_that!(&txt.trim_margin_chomped("#", Chomping::Clip), maybe_some(eq("echo hello\n".to_string())));
+        assert_that!(&txt.trim_margin_chomped("#", Chomping::Keep), maybe_some(eq("echo hello\n\n".to_string())));
+        assert_that!(&"\n  #echo hello\n".trim_margin_chomped("#", Chomping::Clip),
+                     maybe_some(eq("echo hello\n".to_string())));
+    }
+
+    #[test]
+    fn should_fold_margin_lines_into_paragraphs() {
+        let txt = "
+            |The margin of a line is missing
+            |and has to be added.
+            |
+            |Example:
+            |    |a line with margin
+            |
+            |See the documentation.
+        ";
+        assert_that!(&txt.trim_margin_folded("|"),
+                     maybe_some(eq(["The margin of a line is missing and has to be added.",
+                                    "Example:",
+                                    "    |a line with margin",
+                                    "",
+                                    "See the documentation."].join("\n"))));
+    }
+
+    #[test]
+    fn should_report_line_without_margin_when_folding() {
+        let txt = "
+            #first
+            second
+        ";
+        assert_that!(&txt.try_trim_margin_folded("#").map_err(|e| e.line()), maybe_err(eq(3)));
+        assert_that!(&"single line".trim_margin_folded("#"), maybe_some(eq("single line".to_string())));
     }
 }
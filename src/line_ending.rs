@@ -0,0 +1,81 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// The character sequences which are recognised as line breaks in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineBreaks {
+    /// `\n` and `\r\n` terminate a line.
+    #[default]
+    LfOrCrLf,
+    /// `\n`, `\r\n` and a lone `\r` terminate a line.
+    Any,
+}
+
+impl LineBreaks {
+    /// Splits off the first line of `text` and returns it together with its line break and the remaining text.
+    pub(crate) fn split_line(self, text: &str) -> Option<(&str, &str, &str)> {
+        let idx = match self {
+            LineBreaks::LfOrCrLf => text.find('\n')?,
+            LineBreaks::Any => text.find(['\n', '\r'])?,
+        };
+
+        let start = if self == LineBreaks::LfOrCrLf && text[..idx].ends_with('\r') { idx - 1 } else { idx };
+        let end = if text[idx..].starts_with("\r\n") { idx + 2 } else { idx + 1 };
+        Some((&text[..start], &text[start..end], &text[end..]))
+    }
+}
+
+/// The line terminator written between the lines of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// Each line is terminated by `\n`.
+    Lf,
+    /// Each line is terminated by `\r\n`.
+    CrLf,
+    /// Each line is terminated by the line break it had in the input.
+    #[default]
+    Preserve,
+}
+
+impl LineEnding {
+    /// Returns the terminator for a line which was terminated by `original` in the input.
+    pub(crate) fn terminator(self, original: &str) -> &str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Preserve => original,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use galvanic_assert::matchers::*;
+    use super::*;
+
+    #[test]
+    fn should_split_at_lf_and_crlf() {
+        assert_that!(&LineBreaks::LfOrCrLf.split_line("a\nb"), eq(Some(("a", "\n", "b"))));
+        assert_that!(&LineBreaks::LfOrCrLf.split_line("a\r\nb"), eq(Some(("a", "\r\n", "b"))));
+        assert_that!(&LineBreaks::LfOrCrLf.split_line("a\rb"), eq(None));
+    }
+
+    #[test]
+    fn should_split_at_lone_cr_if_requested() {
+        assert_that!(&LineBreaks::Any.split_line("a\rb\nc"), eq(Some(("a", "\r", "b\nc"))));
+        assert_that!(&LineBreaks::Any.split_line("a\r\nb"), eq(Some(("a", "\r\n", "b"))));
+    }
+}
@@ -0,0 +1,620 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::string::String;
+use core::fmt;
+use error::{ErrorKind, Fault, MarginError, TrimIntoError};
+use options::{Chomping, ContinuationJoin, Policy};
+use pattern::Margin;
+
+
+/// Checks if a line contains only whitespace, tabs, etc.
+pub(crate) fn is_blank(line: &str) -> bool {
+    line.trim_start().is_empty()
+}
+
+/// Removes the indentation and the `prefix` from a line as well as everything from the last `right_delimiter` on.
+pub(crate) fn strip_margin<'a, 'p>(line: RawLine<'a>, prefix: &Margin<'p>, right_delimiter: Option<&'p str>,
+                                   policy: &Policy) -> Result<&'a str, Fault<'a, 'p>> {
+    let content = policy.indentation.trim(line.text);
+    if let Some(stripped) = prefix.strip(content) {
+        return match right_delimiter {
+            Some(delimiter) => strip_right_margin(line, stripped, delimiter, policy),
+            None => Ok(stripped),
+        };
+    }
+    if policy.blank_lines_without_prefix && is_blank(line.text) {
+        return Ok("");
+    }
+
+    let column = line.text.len() - content.len();
+    Err(Fault { kind: ErrorKind::MissingPrefix, line, column, prefix: prefix.clone() })
+}
+
+/// Returns the byte offset at which `slice`, a slice of `text`, starts in `text`.
+fn offset_in(text: &str, slice: &str) -> usize {
+    slice.as_ptr() as usize - text.as_ptr() as usize
+}
+
+/// Removes everything from the last `delimiter` on from the `content` of a line.
+fn strip_right_margin<'a, 'p>(line: RawLine<'a>, content: &'a str, delimiter: &'p str,
+                              policy: &Policy) -> Result<&'a str, Fault<'a, 'p>> {
+    match content.rfind(delimiter) {
+        Some(end) => Ok(&content[..end]),
+        None if policy.lines_without_right_delimiter => Ok(content),
+        None => Err(Fault { kind: ErrorKind::MissingRightDelimiter, line, column: line.text.len(), prefix: delimiter.into() }),
+    }
+}
+
+/// A single line of the input together with its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RawLine<'a> {
+    /// The 1-based line number.
+    pub number: usize,
+    /// The byte offset at which the line starts in the input.
+    pub offset: usize,
+    /// The line without its line break.
+    pub text: &'a str,
+    /// The line break terminating the line, empty for the last line.
+    pub ending: &'a str,
+}
+
+/// An iterator over the lines of a multi-line string which skips blank lines at its start and end.
+#[derive(Debug, Clone)]
+pub(crate) struct RawLines<'a> {
+    rest: Option<&'a str>,
+    offset: usize,
+    number: usize,
+    policy: Policy,
+}
+
+impl<'a> RawLines<'a> {
+    /// Creates the iterator or returns `None` if `input` does not contain a line break.
+    ///
+    /// Lines are split and blank lines are skipped according to the `policy`.
+    pub fn new(input: &'a str, policy: &Policy) -> Option<RawLines<'a>> {
+        policy.line_breaks.split_line(input)?;
+
+        let mut lines = RawLines { rest: Some(input), offset: 0, number: 1, policy: *policy };
+        for _ in 0..policy.leading_blank_lines {
+            if !lines.clone().next_line().is_some_and(|line| is_blank(line.text)) {
+                break;
+            }
+            lines.next_line();
+        }
+        Some(lines)
+    }
+
+    /// Returns the next line without considering whether it is a blank line at the end.
+    fn next_line(&mut self) -> Option<RawLine<'a>> {
+        let rest = self.rest?;
+        let (text, ending, rest) = match self.policy.line_breaks.split_line(rest) {
+            Some((text, ending, rest)) => (text, ending, Some(rest)),
+            None => (rest, "", None),
+        };
+
+        let line = RawLine { number: self.number, offset: self.offset, text, ending };
+        self.rest = rest;
+        self.offset += text.len() + ending.len();
+        self.number += 1;
+        Some(line)
+    }
+
+    /// Checks if the previously returned blank line is one of the blank lines to skip at the end.
+    fn is_at_trailing_blank_lines(&self) -> bool {
+        if self.policy.trailing_blank_lines == 0 {
+            return false;
+        }
+
+        let mut lookahead = self.clone();
+        for _ in 1..self.policy.trailing_blank_lines {
+            match lookahead.next_line() {
+                Some(line) if !is_blank(line.text) => return false,
+                Some(_) => {},
+                None => return true,
+            }
+        }
+        lookahead.rest.is_none()
+    }
+}
+
+impl<'a> Iterator for RawLines<'a> {
+    type Item = RawLine<'a>;
+
+    fn next(&mut self) -> Option<RawLine<'a>> {
+        let line = self.next_line()?;
+        if is_blank(line.text) && self.is_at_trailing_blank_lines() {
+            self.rest = None;
+            return None;
+        }
+        Some(line)
+    }
+}
+
+
+/// The reasons why `MarginLines::render` fails.
+#[derive(Debug)]
+pub(crate) enum RenderError<'a, 'p> {
+    /// A line could not be trimmed.
+    Margin(Fault<'a, 'p>),
+    /// The writer reported an error.
+    Write,
+}
+
+impl<'a, 'p> From<Fault<'a, 'p>> for RenderError<'a, 'p> {
+    fn from(fault: Fault<'a, 'p>) -> RenderError<'a, 'p> { RenderError::Margin(fault) }
+}
+
+impl<'a, 'p> From<fmt::Error> for RenderError<'a, 'p> {
+    fn from(_: fmt::Error) -> RenderError<'a, 'p> { RenderError::Write }
+}
+
+impl<'a, 'p> From<RenderError<'a, 'p>> for TrimIntoError {
+    fn from(error: RenderError<'a, 'p>) -> TrimIntoError {
+        match error {
+            RenderError::Margin(fault) => fault.into(),
+            RenderError::Write => TrimIntoError::Write,
+        }
+    }
+}
+
+
+/// A lazy iterator over the lines of a multi-line string with their margin removed.
+///
+/// Created by `MarginTrimmable::margin_lines` or `MarginTrimmable::margin_lines_opts`.
+/// The yielded lines are slices of the input, i.e., iterating does not allocate.
+/// The same rules as in `MarginTrimmable::trim_margin_with` apply:
+/// a blank first and last line are skipped and strings without line break are yielded unmodified.
+/// After the first line without margin prefix an error is yielded and the iteration ends.
+#[derive(Debug, Clone)]
+pub struct MarginLines<'a> {
+    verbatim: Option<&'a str>,
+    lines: Option<RawLines<'a>>,
+    prefix: Margin<'a>,
+    right_delimiter: Option<&'a str>,
+    policy: Policy,
+}
+
+impl<'a> MarginLines<'a> {
+    pub(crate) fn new<M: Into<Margin<'a>>>(input: &'a str, prefix: M, policy: &Policy) -> MarginLines<'a> {
+        let (verbatim, lines) = match RawLines::new(input, policy) {
+            Some(lines) => (None, Some(lines)),
+            None => (Some(input), None),
+        };
+        MarginLines { verbatim, lines, prefix: prefix.into(), right_delimiter: None, policy: *policy }
+    }
+
+    /// Sets the delimiter which closes the margin at the end of every line.
+    pub(crate) fn with_right_delimiter(mut self, right_delimiter: Option<&'a str>) -> MarginLines<'a> {
+        self.right_delimiter = right_delimiter;
+        self
+    }
+
+    /// Returns the next line of the input together with its content inside the margin.
+    fn next_line(&mut self) -> Option<Result<(RawLine<'a>, &'a str), Fault<'a, 'a>>> {
+        let line = self.lines.as_mut()?.next()?;
+        let content = strip_margin(line, &self.prefix, self.right_delimiter, &self.policy).map(|content| (line, content));
+        if content.is_err() {
+            self.lines = None;
+        }
+        Some(content)
+    }
+
+    /// Returns the number of the last line whose content is not blank, looking only up to the first error.
+    fn last_non_blank_line(mut self) -> Option<usize> {
+        let mut last = None;
+        while let Some(Ok((line, content))) = self.next_line() {
+            if !is_blank(content) {
+                last = Some(line.number);
+            }
+        }
+        last
+    }
+
+    /// Writes the remaining lines joined according to the continuation, line ending and chomping policy to `out`.
+    ///
+    /// Nothing is buffered, so the lines before an error have already been written.
+    pub(crate) fn render<'p, W: fmt::Write>(mut self, continuation: Option<&'p str>,
+                                            out: &mut W) -> Result<(), RenderError<'a, 'p>>
+        where 'a: 'p {
+        if let Some(input) = self.verbatim.take() {
+            return Ok(out.write_str(input)?);
+        }
+
+        // trailing blank lines are only written if a line with content follows
+        let last_line = match self.policy.chomping {
+            Some(Chomping::Strip) | Some(Chomping::Clip) => self.clone().last_non_blank_line(),
+            _ => Some(usize::MAX),
+        };
+        let join = self.policy.continuation_join;
+        let mut terminator = None;
+        let mut line_break = "\n";
+        // the continued line together with the column of its continuation marker
+        let mut continued: Option<(RawLine, usize)> = None;
+        // the space joining a continued line is only written before content, so no trailing blank is left
+        let mut pending_space = false;
+        while let Some(line) = self.next_line() {
+            let (line, mut content) = line?;
+            // a continued line is joined with the next line even if chomping drops the lines after it
+            if continued.is_none() && last_line.is_none_or(|last| line.number > last) {
+                continue;
+            }
+            if continued.is_none() {
+                if let Some(terminator) = terminator {
+                    out.write_str(terminator)?;
+                }
+            } else if join == ContinuationJoin::Space {
+                content = content.trim_start();
+            }
+
+            continued = None;
+            if let Some(stripped) = continuation.and_then(|marker| content.trim_end().strip_suffix(marker)) {
+                continued = Some((line, offset_in(line.text, stripped) + stripped.len()));
+                content = if join == ContinuationJoin::Space { stripped.trim_end() } else { stripped };
+            }
+            if pending_space && !content.is_empty() {
+                out.write_char(' ')?;
+            }
+            out.write_str(content)?;
+            pending_space = continued.is_some() && join == ContinuationJoin::Space;
+
+            if !line.ending.is_empty() {
+                line_break = line.ending;
+            }
+            terminator = Some(self.policy.line_ending.terminator(line_break));
+        }
+
+        if let (Some((line, column)), Some(marker)) = (continued, continuation) {
+            return Err(Fault { kind: ErrorKind::DanglingContinuation, line, column, prefix: marker.into() }.into());
+        }
+        match terminator {
+            Some(terminator) if self.policy.terminates_last_line() => Ok(out.write_str(terminator)?),
+            _ => Ok(()),
+        }
+    }
+
+    /// Joins the remaining lines according to the continuation, line ending and chomping policy.
+    pub(crate) fn into_string(self, continuation: Option<&str>) -> Result<String, MarginError> {
+        let mut trimmed = String::new();
+        match self.render(continuation, &mut trimmed) {
+            Ok(()) => Ok(trimmed),
+            Err(RenderError::Margin(fault)) => Err(fault.into()),
+            Err(RenderError::Write) => unreachable!("writing to a string cannot fail"),
+        }
+    }
+
+    /// Folds the remaining lines into paragraphs like a YAML `>` block scalar.
+    ///
+    /// Consecutive text lines are joined by a space, blank lines become line breaks
+    /// and more-indented lines, i.e., lines starting with a blank, are kept on their own line.
+    pub(crate) fn into_folded(mut self) -> Result<String, MarginError> {
+        if let Some(input) = self.verbatim.take() {
+            return Ok(input.into());
+        }
+
+        let mut folded = String::new();
+        let mut previous_more_indented: Option<bool> = None;
+        let mut empty_lines: usize = 0;
+        let mut line_break = "\n";
+        while let Some(line) = self.next_line() {
+            let (line, content) = line?;
+            let terminator = self.policy.line_ending.terminator(line_break);
+            if !line.ending.is_empty() {
+                line_break = line.ending;
+            }
+            if is_blank(content) {
+                empty_lines += 1;
+                continue;
+            }
+
+            let more_indented = content.starts_with([' ', '\t']);
+            let breaks = match previous_more_indented {
+                None => empty_lines,
+                Some(false) if !more_indented => empty_lines,
+                Some(_) => empty_lines + 1,
+            };
+            if breaks == 0 && previous_more_indented.is_some() {
+                folded.push(' ');
+            }
+            for _ in 0..breaks {
+                folded.push_str(terminator);
+            }
+            folded.push_str(content);
+            previous_more_indented = Some(more_indented);
+            empty_lines = 0;
+        }
+
+        // without text the empty lines are only separated by line breaks, not preceded by them
+        let any_line = usize::from(!folded.is_empty() || empty_lines > 0);
+        if folded.is_empty() {
+            empty_lines = empty_lines.saturating_sub(1);
+        }
+        empty_lines = match self.policy.chomping {
+            None if self.policy.trailing_newline => empty_lines + any_line,
+            None => empty_lines,
+            Some(Chomping::Strip) => 0,
+            Some(Chomping::Clip) => usize::from(!folded.is_empty()),
+            Some(Chomping::Keep) => empty_lines + any_line,
+        };
+        for _ in 0..empty_lines {
+            folded.push_str(self.policy.line_ending.terminator(line_break));
+        }
+        Ok(folded)
+    }
+}
+
+impl<'a> Iterator for MarginLines<'a> {
+    type Item = Result<&'a str, MarginError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(input) = self.verbatim.take() {
+            return Some(Ok(input));
+        }
+        self.next_line().map(|line| line.map(|(_, content)| content).map_err(MarginError::from))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use galvanic_assert::matchers::*;
+    use line_ending::LineBreaks;
+    use options::Indentation;
+    use super::*;
+
+    #[test]
+    fn should_yield_slices_of_the_input() {
+        let txt = "
+            |first
+            |second
+        ";
+        let lines: Vec<_> = MarginLines::new(txt, "|", &Policy::default()).collect();
+        assert_that!(&lines, eq(vec![Ok("first"), Ok("second")]));
+    }
+
+    #[test]
+    fn should_yield_single_line_string_unmodified() {
+        let lines: Vec<_> = MarginLines::new("|hello", "|", &Policy::default()).collect();
+        assert_that!(&lines, eq(vec![Ok("|hello")]));
+    }
+
+    #[test]
+    fn should_stop_after_line_without_margin() {
+        let txt = "
+            |first
+            second
+            |third
+        ";
+        let lines: Vec<_> = MarginLines::new(txt, "|", &Policy::default()).map(|l| l.map_err(|e| e.line())).collect();
+        assert_that!(&lines, eq(vec![Ok("first"), Err(3)]));
+    }
+
+    #[test]
+    fn should_number_lines_including_skipped_first_line() {
+        let lines: Vec<_> = RawLines::new("\n  a\nb\n  ", &Policy::default()).unwrap().map(|l| (l.number, l.offset)).collect();
+        assert_that!(&lines, eq(vec![(2, 1), (3, 5)]));
+    }
+
+    #[test]
+    fn should_treat_crlf_as_line_break() {
+        let txt = "\r\n  |first\r\n  |second\r\n  |\r\n  ";
+        let lines: Vec<_> = MarginLines::new(txt, "|", &Policy::default()).collect();
+        assert_that!(&lines, eq(vec![Ok("first"), Ok("second"), Ok("")]));
+    }
+
+    #[test]
+    fn should_treat_lone_cr_as_line_break_if_requested() {
+        let policy = Policy { line_breaks: LineBreaks::Any, ..Policy::default() };
+        let lines: Vec<_> = RawLines::new("\r|a\r\n|b\r", &policy).unwrap()
+            .map(|l| (l.text, l.ending))
+            .collect();
+        assert_that!(&lines, eq(vec![("|a", "\r\n"), ("|b", "\r")]));
+    }
+
+    #[test]
+    fn should_skip_configured_number_of_blank_lines() {
+        let policy = Policy { leading_blank_lines: 2, trailing_blank_lines: 2, ..Policy::default() };
+        let lines: Vec<_> = RawLines::new("\n\n\n|a\n\n\n", &policy).unwrap().map(|l| l.number).collect();
+        assert_that!(&lines, eq(vec![3, 4, 5]));
+    }
+
+    #[test]
+    fn should_keep_blank_lines_if_none_are_to_be_skipped() {
+        let policy = Policy { leading_blank_lines: 0, trailing_blank_lines: 0, ..Policy::default() };
+        let lines: Vec<_> = RawLines::new("\n|a\n", &policy).unwrap().map(|l| l.number).collect();
+        assert_that!(&lines, eq(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn should_report_column_of_expected_prefix() {
+        let policy = Policy { indentation: Indentation::Spaces, ..Policy::default() };
+        let line = RawLine { number: 2, offset: 10, text: "  \t|a", ending: "\n" };
+        let error = MarginError::from(strip_margin(line, &Margin::Literal("|"), None, &policy).unwrap_err());
+        assert_that!(&error.offset(), eq(12));
+        assert_that!(&error.column(), eq(2));
+    }
+
+    #[test]
+    fn should_keep_content_up_to_last_right_delimiter() {
+        let txt = "
+            |two spaces  |
+            |a | b |  
+            ||
+        ";
+        let lines: Vec<_> = MarginLines::new(txt, "|", &Policy::default()).with_right_delimiter(Some("|")).collect();
+        assert_that!(&lines, eq(vec![Ok("two spaces  "), Ok("a | b "), Ok("")]));
+    }
+
+    #[test]
+    fn should_report_or_pass_through_line_without_right_delimiter() {
+        let line = RawLine { number: 3, offset: 20, text: "  |open  ", ending: "\n" };
+        let error = MarginError::from(strip_margin(line, &Margin::Literal("|"), Some("|"), &Policy::default()).unwrap_err());
+        assert_that!(&error.kind(), eq(ErrorKind::MissingRightDelimiter));
+        assert_that!(&error.column(), eq(9));
+        assert_that!(&error.offset(), eq(29));
+
+        let policy = Policy { lines_without_right_delimiter: true, ..Policy::default() };
+        assert_that!(&strip_margin(line, &Margin::Literal("|"), Some("|"), &policy).ok(), eq(Some("open  ")));
+    }
+
+    #[test]
+    fn should_join_continued_lines_with_single_space() {
+        let txt = "
+            |SELECT *   \\
+            |    FROM t \\  
+            |  WHERE x
+            |LIMIT 1
+        ";
+        let joined = MarginLines::new(txt, "|", &Policy::default()).into_string(Some("\\"));
+        assert_that!(&joined, eq(Ok("SELECT * FROM t WHERE x\nLIMIT 1".to_string())));
+    }
+
+    #[test]
+    fn should_concatenate_continued_lines_if_requested() {
+        let txt = "
+            |abc\\
+            |  def
+        ";
+        let policy = Policy { continuation_join: ContinuationJoin::Nothing, ..Policy::default() };
+        let joined = MarginLines::new(txt, "|", &policy).into_string(Some("\\"));
+        assert_that!(&joined, eq(Ok("abc  def".to_string())));
+    }
+
+    #[test]
+    fn should_report_dangling_continuation() {
+        let txt = "
+            |first \\
+            |second \\
+        ";
+        let error = MarginLines::new(txt, "|", &Policy::default()).into_string(Some("\\")).unwrap_err();
+        assert_that!(&error.kind(), eq(ErrorKind::DanglingContinuation));
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.column(), eq(20));
+    }
+
+    #[test]
+    fn should_point_at_dangling_continuation_inside_right_delimiter() {
+        for &(txt, delimiter) in &[("\n  |abc \\   <  \n", "<"), ("\n  |abc \\│\n", "│")] {
+            let error = MarginLines::new(txt, "|", &Policy::default())
+                .with_right_delimiter(Some(delimiter))
+                .into_string(Some("\\"))
+                .unwrap_err();
+            assert_that!(&error.kind(), eq(ErrorKind::DanglingContinuation));
+            assert_that!(&error.column(), eq(7));
+            assert_that!(&error.to_string().ends_with("\n       ^"), eq(true));
+        }
+    }
+
+    #[test]
+    fn should_not_report_continuation_before_chomped_lines_as_dangling() {
+        let txt = "\n |a \\\n |\n |\n";
+        let unchomped = MarginLines::new(txt, "|", &Policy::default()).into_string(Some("\\"));
+        assert_that!(&unchomped, eq(Ok("a\n".to_string())));
+        for &chomping in &[Chomping::Strip, Chomping::Clip, Chomping::Keep] {
+            let policy = Policy { chomping: Some(chomping), ..Policy::default() };
+            assert_that!(&MarginLines::new(txt, "|", &policy).into_string(Some("\\")).is_ok(), eq(true));
+        }
+        let policy = Policy { chomping: Some(Chomping::Strip), ..Policy::default() };
+        assert_that!(&MarginLines::new(txt, "|", &policy).into_string(Some("\\")), eq(Ok("a".to_string())));
+    }
+
+    #[test]
+    fn should_not_join_continued_line_with_empty_line_by_space() {
+        let txt = "\n |a \\\n |\n |b\n";
+        assert_that!(&MarginLines::new(txt, "|", &Policy::default()).into_string(Some("\\")), eq(Ok("a\nb".to_string())));
+        let txt = "\n |a \\\n |  \\\n |b\n";
+        assert_that!(&MarginLines::new(txt, "|", &Policy::default()).into_string(Some("\\")), eq(Ok("a b".to_string())));
+    }
+
+    #[test]
+    fn should_fold_empty_lines_like_joining_them() {
+        let txt = "\n |\n |\n";
+        let keep = Policy { chomping: Some(Chomping::Keep), ..Policy::default() };
+        let terminated = Policy { trailing_newline: true, ..Policy::default() };
+        for policy in &[keep, terminated, Policy::default()] {
+            let joined = MarginLines::new(txt, "|", policy).into_string(None);
+            assert_that!(&MarginLines::new(txt, "|", policy).into_folded(), eq(joined));
+        }
+        assert_that!(&MarginLines::new(txt, "|", &keep).into_folded(), eq(Ok("\n\n".to_string())));
+    }
+
+    #[test]
+    fn should_fold_lines_into_paragraphs() {
+        let txt = "
+            |first paragraph
+            |continues here
+            |
+            |second paragraph
+            |
+            |
+            |third paragraph
+        ";
+        let folded = MarginLines::new(txt, "|", &Policy::default()).into_folded();
+        assert_that!(&folded, eq(Ok("first paragraph continues here\nsecond paragraph\n\nthird paragraph".to_string())));
+    }
+
+    #[test]
+    fn should_keep_more_indented_lines_verbatim() {
+        let txt = "
+            |example:
+            |  let x = 1;
+            |  let y = 2;
+            |
+            |done
+            |folded
+        ";
+        let folded = MarginLines::new(txt, "|", &Policy::default()).into_folded();
+        assert_that!(&folded, eq(Ok("example:\n  let x = 1;\n  let y = 2;\n\ndone folded".to_string())));
+    }
+
+    #[test]
+    fn should_keep_trailing_empty_lines_when_folding() {
+        let txt = "\r\n  |first\r\n  |second\r\n  |\r\n  ";
+        let folded = MarginLines::new(txt, "|", &Policy::default()).into_folded();
+        assert_that!(&folded, eq(Ok("first second\r\n".to_string())));
+        let policy = Policy { trailing_newline: true, ..Policy::default() };
+        let folded = MarginLines::new(txt, "|", &policy).into_folded();
+        assert_that!(&folded, eq(Ok("first second\r\n\r\n".to_string())));
+        let policy = Policy { chomping: Some(Chomping::Clip), ..Policy::default() };
+        let folded = MarginLines::new(txt, "|", &policy).into_folded();
+        assert_that!(&folded, eq(Ok("first second\r\n".to_string())));
+    }
+
+    #[test]
+    fn should_chomp_trailing_empty_lines() {
+        let txt = "
+            |first
+            |
+            |  
+            |
+        ";
+        let chomp = |chomping| {
+            let policy = Policy { chomping: Some(chomping), ..Policy::default() };
+            MarginLines::new(txt, "|", &policy).into_string(None).unwrap()
+        };
+        assert_that!(&chomp(Chomping::Strip), eq("first".to_string()));
+        assert_that!(&chomp(Chomping::Clip), eq("first\n".to_string()));
+        assert_that!(&chomp(Chomping::Keep), eq("first\n\n  \n\n".to_string()));
+    }
+
+    #[test]
+    fn should_not_terminate_empty_result_when_clipping() {
+        let policy = Policy { chomping: Some(Chomping::Clip), ..Policy::default() };
+        let clipped = MarginLines::new("\n  |\n  |\n", "|", &policy).into_string(None);
+        assert_that!(&clipped, eq(Ok(String::new())));
+    }
+}
@@ -0,0 +1,282 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `trim-margin` command-line tool applies the trimming rules of the library to files or stdin.
+
+#[cfg(test)] #[macro_use] extern crate galvanic_assert;
+extern crate trim_margin;
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+use trim_margin::{Chomping, ContinuationJoin, Indentation, LineBreaks, LineEnding, MarginTrimmable, TrimOptions};
+
+
+const USAGE: &str = "\
+usage: trim-margin [OPTIONS] [FILE...]
+
+Removes the margin of the given files (or stdin if no file or `-` is given) and writes the result to stdout.
+
+options:
+  -p, --prefix PREFIX          the margin prefix (default: |)
+      --leading-blank-lines N  blank lines removed at the start (default: 1)
+      --trailing-blank-lines N blank lines removed at the end (default: 1)
+      --lenient                allow blank lines without margin prefix
+      --right-delimiter DELIM  keep the content of each line up to the last DELIM
+      --lenient-right          allow lines without right delimiter
+      --indentation KIND       blanks allowed before the prefix: whitespace, spaces-and-tabs, spaces, none
+      --line-breaks KIND       line breaks of the input: lf-or-crlf, any
+      --line-ending KIND       line endings of the output: lf, crlf, preserve
+      --trailing-newline       terminate the last line with a line break
+      --chomping KIND          line breaks at the end: strip, clip, keep
+      --continuation MARKER    join lines ending with MARKER with the next line
+      --continuation-join KIND how continued lines are joined: space, nothing
+  -i, --in-place               overwrite the files instead of writing to stdout
+  -h, --help                   print this help";
+
+
+/// The parsed command-line arguments.
+#[derive(Debug, Default)]
+struct Args {
+    options: TrimOptions,
+    in_place: bool,
+    help: bool,
+    files: Vec<String>,
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("invalid number of blank lines: {}", value))
+}
+
+fn parse_indentation(value: &str) -> Result<Indentation, String> {
+    match value {
+        "whitespace" => Ok(Indentation::Whitespace),
+        "spaces-and-tabs" => Ok(Indentation::SpacesAndTabs),
+        "spaces" => Ok(Indentation::Spaces),
+        "none" => Ok(Indentation::None),
+        _ => Err(format!("invalid indentation: {}", value)),
+    }
+}
+
+fn parse_line_breaks(value: &str) -> Result<LineBreaks, String> {
+    match value {
+        "lf-or-crlf" => Ok(LineBreaks::LfOrCrLf),
+        "any" => Ok(LineBreaks::Any),
+        _ => Err(format!("invalid line breaks: {}", value)),
+    }
+}
+
+fn parse_line_ending(value: &str) -> Result<LineEnding, String> {
+    match value {
+        "lf" => Ok(LineEnding::Lf),
+        "crlf" => Ok(LineEnding::CrLf),
+        "preserve" => Ok(LineEnding::Preserve),
+        _ => Err(format!("invalid line ending: {}", value)),
+    }
+}
+
+fn parse_chomping(value: &str) -> Result<Chomping, String> {
+    match value {
+        "strip" => Ok(Chomping::Strip),
+        "clip" => Ok(Chomping::Clip),
+        "keep" => Ok(Chomping::Keep),
+        _ => Err(format!("invalid chomping: {}", value)),
+    }
+}
+
+fn parse_continuation_join(value: &str) -> Result<ContinuationJoin, String> {
+    match value {
+        "space" => Ok(ContinuationJoin::Space),
+        "nothing" => Ok(ContinuationJoin::Nothing),
+        _ => Err(format!("invalid continuation join: {}", value)),
+    }
+}
+
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            parsed.files.extend(args);
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            parsed.files.push(arg);
+            continue;
+        }
+
+        let (name, inline_value) = match arg.find('=') {
+            Some(idx) if arg.starts_with("--") => (&arg[..idx], Some(arg[idx + 1..].to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || inline_value.clone()
+            .or_else(|| args.next())
+            .ok_or_else(|| format!("missing value for {}", name));
+        let flag = || match inline_value {
+            Some(_) => Err(format!("option takes no value: {}", name)),
+            None => Ok(()),
+        };
+
+        let options = parsed.options.clone();
+        parsed.options = match name {
+            "-p" | "--prefix" => options.prefix(value()?),
+            "--leading-blank-lines" => options.leading_blank_lines(parse_count(&value()?)?),
+            "--trailing-blank-lines" => options.trailing_blank_lines(parse_count(&value()?)?),
+            "--lenient" => { flag()?; options.blank_lines_without_prefix(true) },
+            "--right-delimiter" => options.right_delimiter(value()?),
+            "--lenient-right" => { flag()?; options.lines_without_right_delimiter(true) },
+            "--indentation" => options.indentation(parse_indentation(&value()?)?),
+            "--line-breaks" => options.line_breaks(parse_line_breaks(&value()?)?),
+            "--line-ending" => options.line_ending(parse_line_ending(&value()?)?),
+            "--trailing-newline" => { flag()?; options.trailing_newline(true) },
+            "--chomping" => options.chomping(parse_chomping(&value()?)?),
+            "--continuation" => options.continuation(value()?),
+            "--continuation-join" => options.continuation_join(parse_continuation_join(&value()?)?),
+            "-i" | "--in-place" => { flag()?; parsed.in_place = true; options },
+            "-h" | "--help" => { flag()?; parsed.help = true; options },
+            _ => return Err(format!("unknown option: {}", name)),
+        };
+    }
+
+    if parsed.files.is_empty() {
+        parsed.files.push("-".into());
+    }
+    if parsed.in_place && parsed.files.iter().any(|file| file == "-") {
+        return Err("stdin cannot be trimmed in place".into());
+    }
+    Ok(parsed)
+}
+
+/// Trims the `content` of the file `name` and reports a missing margin as `file:line: message`.
+fn trim(name: &str, content: &str, options: &TrimOptions) -> Result<String, String> {
+    content.try_trim_margin_opts(options)
+        .map_err(|error| format!("{}:{}: {}", name, error.line(), error.message()))
+}
+
+fn read(file: &str) -> io::Result<String> {
+    let mut content = String::new();
+    if file == "-" {
+        io::stdin().read_to_string(&mut content)?;
+    } else {
+        content = fs::read_to_string(file)?;
+    }
+    Ok(content)
+}
+
+/// Trims all files of `args` and returns whether all of them could be trimmed.
+fn run(args: &Args) -> bool {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut success = true;
+    for file in &args.files {
+        let name = if file == "-" { "<stdin>" } else { file.as_str() };
+        let result = read(file)
+            .map_err(|error| format!("{}: {}", name, error))
+            .and_then(|content| trim(name, &content, &args.options))
+            .and_then(|trimmed| {
+                let written = if args.in_place {
+                    fs::write(file, trimmed)
+                } else {
+                    stdout.write_all(trimmed.as_bytes())
+                };
+                written.map_err(|error| format!("{}: {}", name, error))
+            });
+        if let Err(message) = result {
+            eprintln!("trim-margin: {}", message);
+            success = false;
+        }
+    }
+    success
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("trim-margin: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        },
+    };
+
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if !run(&args) {
+        process::exit(1);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn should_read_stdin_by_default() {
+        let args = parse(&[]).unwrap();
+        assert_that!(&args.files, eq(vec!["-".to_string()]));
+        assert_that!(&args.options, eq(TrimOptions::default()));
+    }
+
+    #[test]
+    fn should_parse_trimming_options() {
+        let args = parse(&["--prefix", "#", "--leading-blank-lines=0", "--lenient", "--indentation", "spaces",
+                           "--line-ending=lf", "--chomping=clip", "--continuation", "\\",
+                           "--continuation-join=nothing", "-i", "a.txt", "--", "-b.txt"]).unwrap();
+        let expected = TrimOptions::new()
+            .prefix("#")
+            .leading_blank_lines(0)
+            .blank_lines_without_prefix(true)
+            .indentation(Indentation::Spaces)
+            .line_ending(LineEnding::Lf)
+            .chomping(Chomping::Clip)
+            .continuation("\\")
+            .continuation_join(ContinuationJoin::Nothing);
+        assert_that!(&args.options, eq(expected));
+        assert_that!(&args.in_place, eq(true));
+        assert_that!(&args.files, eq(vec!["a.txt".to_string(), "-b.txt".to_string()]));
+    }
+
+    #[test]
+    fn should_reject_invalid_arguments() {
+        assert_that!(&parse(&["--unknown"]).map(|_| ()), maybe_err(eq("unknown option: --unknown".to_string())));
+        assert_that!(&parse(&["--prefix"]).map(|_| ()), maybe_err(eq("missing value for --prefix".to_string())));
+        assert_that!(&parse(&["--line-ending", "cr"]).map(|_| ()), maybe_err(eq("invalid line ending: cr".to_string())));
+        assert_that!(&parse(&["-i"]).map(|_| ()), maybe_err(eq("stdin cannot be trimmed in place".to_string())));
+    }
+
+    #[test]
+    fn should_reject_value_of_flags() {
+        for flag in &["--lenient", "--lenient-right", "--trailing-newline", "--in-place", "--help"] {
+            let arg = format!("{}=false", flag);
+            assert_that!(&parse(&[&arg, "a.txt"]).map(|_| ()), maybe_err(eq(format!("option takes no value: {}", flag))));
+        }
+    }
+
+    #[test]
+    fn should_report_file_and_line_of_missing_margin() {
+        let content = "\n  |first\n  second\n";
+        assert_that!(&trim("a.txt", content, &TrimOptions::default()),
+                     maybe_err(eq("a.txt:3: expected margin prefix \"|\"".to_string())));
+    }
+}
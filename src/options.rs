@@ -0,0 +1,324 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::string::String;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use line_ending::{LineBreaks, LineEnding};
+#[cfg(target_has_atomic = "ptr")]
+use pattern::{MarginPattern, SharedPattern};
+use pattern::Margin;
+
+
+/// The characters which may precede the margin prefix of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Indentation {
+    /// Any Unicode whitespace.
+    #[default]
+    Whitespace,
+    /// Spaces and tabs.
+    SpacesAndTabs,
+    /// Spaces only.
+    Spaces,
+    /// Nothing, i.e., the margin prefix has to start in the first column.
+    None,
+}
+
+impl Indentation {
+    /// Removes the leading indentation from `line`.
+    pub(crate) fn trim(self, line: &str) -> &str {
+        match self {
+            Indentation::Whitespace => line.trim_start(),
+            Indentation::SpacesAndTabs => line.trim_start_matches([' ', '\t']),
+            Indentation::Spaces => line.trim_start_matches(' '),
+            Indentation::None => line,
+        }
+    }
+}
+
+
+/// How a line ending with a continuation marker is joined with the next line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContinuationJoin {
+    /// The blanks around the line break are replaced by a single space, which is omitted if the next line is empty.
+    #[default]
+    Space,
+    /// The lines are concatenated as they are.
+    Nothing,
+}
+
+
+/// How the line breaks at the end of the result are treated, following the chomping indicators of YAML block scalars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chomping {
+    /// The last line is not terminated and trailing empty lines are removed.
+    Strip,
+    /// The last non-empty line is terminated by exactly one line break and trailing empty lines are removed.
+    Clip,
+    /// The last line is terminated by a line break and trailing empty lines are kept.
+    Keep,
+}
+
+
+/// The trimming policy apart from the margin prefix and the continuation marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Policy {
+    pub leading_blank_lines: usize,
+    pub trailing_blank_lines: usize,
+    pub blank_lines_without_prefix: bool,
+    pub indentation: Indentation,
+    pub trailing_newline: bool,
+    pub line_breaks: LineBreaks,
+    pub line_ending: LineEnding,
+    pub continuation_join: ContinuationJoin,
+    pub chomping: Option<Chomping>,
+    pub lines_without_right_delimiter: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy {
+            leading_blank_lines: 1,
+            trailing_blank_lines: 1,
+            blank_lines_without_prefix: false,
+            indentation: Indentation::default(),
+            trailing_newline: false,
+            line_breaks: LineBreaks::default(),
+            line_ending: LineEnding::default(),
+            continuation_join: ContinuationJoin::default(),
+            chomping: None,
+            lines_without_right_delimiter: false,
+        }
+    }
+}
+
+impl Policy {
+    /// Checks if the last line of the result is terminated by a line break.
+    pub fn terminates_last_line(&self) -> bool {
+        match self.chomping {
+            None => self.trailing_newline,
+            Some(Chomping::Strip) => false,
+            Some(Chomping::Clip) | Some(Chomping::Keep) => true,
+        }
+    }
+}
+
+
+/// A builder for configuring how the margin of a multi-line string is trimmed.
+///
+/// The default options reproduce the behaviour of `MarginTrimmable::trim_margin`.
+///
+/// ```
+/// extern crate trim_margin;
+/// use trim_margin::{MarginTrimmable, TrimOptions};
+///
+/// fn main() {
+///     let options = TrimOptions::new()
+///         .prefix("#")
+///         .trailing_blank_lines(2)
+///         .trailing_newline(true);
+///     let script = "
+///         #echo hello
+///
+///     ".trim_margin_opts(&options).unwrap();
+///     assert_eq!(script, "echo hello\n");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrimOptions {
+    prefix: String,
+    continuation: Option<String>,
+    right_delimiter: Option<String>,
+    policy: Policy,
+    #[cfg(target_has_atomic = "ptr")]
+    pattern: Option<SharedPattern>,
+}
+
+impl TrimOptions {
+    /// Creates the default options, i.e., `|` as margin prefix and the policy of `MarginTrimmable::trim_margin`.
+    pub fn new() -> TrimOptions {
+        TrimOptions {
+            prefix: "|".into(),
+            continuation: None,
+            right_delimiter: None,
+            policy: Policy::default(),
+            #[cfg(target_has_atomic = "ptr")]
+            pattern: None,
+        }
+    }
+
+    /// Sets the margin prefix which has to start every line.
+    ///
+    /// It replaces a previously set `prefix_pattern`.
+    pub fn prefix<M: AsRef<str>>(mut self, prefix: M) -> TrimOptions {
+        self.prefix = prefix.as_ref().into();
+        #[cfg(target_has_atomic = "ptr")]
+        {
+            self.pattern = None;
+        }
+        self
+    }
+
+    /// Sets a `MarginPattern` as margin prefix instead of the literal `prefix`, e.g., a set of `char`s.
+    ///
+    /// The pattern is shared by the clones of the options, which are only equal if they share the same pattern.
+    /// `MarginTrimmable::add_margin_opts` still adds the literal `prefix`.
+    ///
+    /// ```
+    /// extern crate trim_margin;
+    /// use trim_margin::{Chomping, MarginTrimmable, TrimOptions};
+    ///
+    /// fn main() {
+    ///     let options = TrimOptions::new().prefix_pattern(['│', '¦']).chomping(Chomping::Clip);
+    ///     let table = "
+    ///         │name ¦ value
+    ///         ¦size ¦ 42
+    ///     ";
+    ///     assert_eq!(table.trim_margin_opts(&options), Some("name ¦ value\nsize ¦ 42\n".to_string()));
+    /// }
+    /// ```
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn prefix_pattern<P: MarginPattern + Send + Sync + 'static>(mut self, pattern: P) -> TrimOptions {
+        self.pattern = Some(SharedPattern(Arc::new(pattern)));
+        self
+    }
+
+    /// Sets a delimiter which closes the margin at the end of every line (default: none).
+    ///
+    /// The content of a line ends at the last occurrence of the delimiter,
+    /// so blanks in front of it are preserved even if editors strip trailing whitespace.
+    pub fn right_delimiter<D: AsRef<str>>(mut self, delimiter: D) -> TrimOptions {
+        self.right_delimiter = Some(delimiter.as_ref().into());
+        self
+    }
+
+    /// Sets whether lines may omit the right delimiter (default: `false`).
+    ///
+    /// Such lines are kept up to their end instead of being reported as error.
+    pub fn lines_without_right_delimiter(mut self, allowed: bool) -> TrimOptions {
+        self.policy.lines_without_right_delimiter = allowed;
+        self
+    }
+
+    /// Sets how many blank lines are removed at the start of the string (default: 1).
+    pub fn leading_blank_lines(mut self, count: usize) -> TrimOptions {
+        self.policy.leading_blank_lines = count;
+        self
+    }
+
+    /// Sets how many blank lines are removed at the end of the string (default: 1).
+    pub fn trailing_blank_lines(mut self, count: usize) -> TrimOptions {
+        self.policy.trailing_blank_lines = count;
+        self
+    }
+
+    /// Sets whether blank lines in between may omit the margin prefix (default: `false`).
+    ///
+    /// Such lines become empty lines in the result.
+    pub fn blank_lines_without_prefix(mut self, allowed: bool) -> TrimOptions {
+        self.policy.blank_lines_without_prefix = allowed;
+        self
+    }
+
+    /// Sets which characters may precede the margin prefix (default: `Indentation::Whitespace`).
+    pub fn indentation(mut self, indentation: Indentation) -> TrimOptions {
+        self.policy.indentation = indentation;
+        self
+    }
+
+    /// Sets whether the last line of the result is terminated by a line break (default: `false`).
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> TrimOptions {
+        self.policy.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Sets how line breaks and empty lines at the end of the result are treated (default: none).
+    ///
+    /// If set, the chomping takes precedence over `trailing_newline`.
+    /// Without chomping, trailing empty lines are kept and the last line is terminated only if `trailing_newline` is set.
+    pub fn chomping(mut self, chomping: Chomping) -> TrimOptions {
+        self.policy.chomping = Some(chomping);
+        self
+    }
+
+    /// Sets the line breaks at which the input is split (default: `LineBreaks::LfOrCrLf`).
+    pub fn line_breaks(mut self, line_breaks: LineBreaks) -> TrimOptions {
+        self.policy.line_breaks = line_breaks;
+        self
+    }
+
+    /// Sets how the lines of the result are terminated (default: `LineEnding::Preserve`).
+    pub fn line_ending(mut self, line_ending: LineEnding) -> TrimOptions {
+        self.policy.line_ending = line_ending;
+        self
+    }
+
+    /// Sets a marker which joins a line ending with it with the next line (default: none).
+    ///
+    /// The marker is removed and may be followed by blanks.
+    /// The last line must not end with the marker.
+    pub fn continuation<C: AsRef<str>>(mut self, marker: C) -> TrimOptions {
+        self.continuation = Some(marker.as_ref().into());
+        self
+    }
+
+    /// Sets how continued lines are joined (default: `ContinuationJoin::Space`).
+    pub fn continuation_join(mut self, join: ContinuationJoin) -> TrimOptions {
+        self.policy.continuation_join = join;
+        self
+    }
+
+    /// Returns the literal margin prefix, which is not used for trimming if a `prefix_pattern` is set.
+    pub fn margin_prefix(&self) -> &str { &self.prefix }
+
+    /// Returns the right delimiter of the margin.
+    pub fn right_margin_delimiter(&self) -> Option<&str> { self.right_delimiter.as_deref() }
+
+    /// Returns the continuation marker.
+    pub fn continuation_marker(&self) -> Option<&str> { self.continuation.as_deref() }
+
+    pub(crate) fn policy(&self) -> &Policy { &self.policy }
+
+    /// Returns the margin prefix used for trimming, i.e., the `prefix_pattern` if set or else the literal prefix.
+    pub(crate) fn margin(&self) -> Margin<'_> {
+        #[cfg(target_has_atomic = "ptr")]
+        {
+            if let Some(ref pattern) = self.pattern {
+                return Margin::Pattern(&*pattern.0);
+            }
+        }
+        Margin::Literal(&self.prefix)
+    }
+}
+
+impl Default for TrimOptions {
+    fn default() -> TrimOptions { TrimOptions::new() }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use galvanic_assert::matchers::*;
+    use super::*;
+
+    #[test]
+    fn should_trim_only_allowed_indentation() {
+        assert_that!(&Indentation::Whitespace.trim("\u{a0} \t|x"), eq("|x"));
+        assert_that!(&Indentation::SpacesAndTabs.trim("\u{a0} \t|x"), eq("\u{a0} \t|x"));
+        assert_that!(&Indentation::SpacesAndTabs.trim(" \t|x"), eq("|x"));
+        assert_that!(&Indentation::Spaces.trim(" \t|x"), eq("\t|x"));
+        assert_that!(&Indentation::None.trim(" |x"), eq(" |x"));
+    }
+}
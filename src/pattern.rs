@@ -0,0 +1,267 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use core::fmt;
+
+
+/// A margin prefix which is matched at the start of every line, e.g., by `MarginTrimmable::trim_margin_with`.
+///
+/// It is implemented for literal strings (`&str`, `&&str`, `String`, `Cow<str>`, `Box<str>`, `Rc<str>`
+/// and `Arc<str>`), single `char`s, sets of `char`s given as `&[char]` or `[char; N]`
+/// and closures `Fn(&str) -> Option<usize>` which return the length of the matched prefix.
+///
+/// All `*_with` methods of `MarginTrimmable` and `margin_lines` accept a `MarginPattern`,
+/// and `TrimOptions::prefix_pattern` sets one for the other options.
+/// `MarginReader`, `AsyncMarginReader`, `AsyncMarginLines` and `add_margin` take a literal prefix.
+/// A generic `AsRef<str>` prefix has to be passed as `prefix.as_ref()`.
+///
+/// ```
+/// extern crate trim_margin;
+/// use trim_margin::MarginTrimmable;
+///
+/// fn main() {
+///     let table = "
+///         │name ¦ value
+///         ¦size ¦ 42
+///     ";
+///     assert_eq!(table.trim_margin_with(['│', '¦']), Some("name ¦ value\nsize ¦ 42".to_string()));
+///     let numbered = "
+///         1: first
+///         2: second
+///     ";
+///     let line_number = |line: &str| line.find(": ").filter(|&idx| idx > 0).map(|idx| idx + 2);
+///     assert_eq!(numbered.trim_margin_with(line_number), Some("first\nsecond".to_string()));
+/// }
+/// ```
+pub trait MarginPattern {
+    /// Returns the length in bytes of the margin prefix at the start of `text` or `None` if `text` does not start with it.
+    ///
+    /// `text` is a line of the input whose leading blanks have already been removed.
+    fn prefix_len(&self, text: &str) -> Option<usize>;
+
+    /// Describes the pattern in error messages, see `MarginError::prefix`.
+    fn description(&self) -> Cow<'_, str>;
+}
+
+impl MarginPattern for &str {
+    fn prefix_len(&self, text: &str) -> Option<usize> {
+        if text.starts_with(*self) { Some(self.len()) } else { None }
+    }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for String {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self.as_str().prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for &String {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self.as_str().prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for &&str {
+    fn prefix_len(&self, text: &str) -> Option<usize> { (**self).prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for Cow<'_, str> {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self.as_ref().prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for &Cow<'_, str> {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self.as_ref().prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for Box<str> {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self.as_ref().prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for Rc<str> {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self.as_ref().prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl MarginPattern for Arc<str> {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self.as_ref().prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self) }
+}
+
+impl MarginPattern for char {
+    fn prefix_len(&self, text: &str) -> Option<usize> {
+        if text.starts_with(*self) { Some(self.len_utf8()) } else { None }
+    }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Owned(self.to_string()) }
+}
+
+impl MarginPattern for &[char] {
+    fn prefix_len(&self, text: &str) -> Option<usize> {
+        text.chars().next().filter(|c| self.contains(c)).map(char::len_utf8)
+    }
+
+    fn description(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("[{}]", self.iter().collect::<String>()))
+    }
+}
+
+impl<const N: usize> MarginPattern for [char; N] {
+    fn prefix_len(&self, text: &str) -> Option<usize> { (&self[..]).prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("[{}]", self.iter().collect::<String>()))
+    }
+}
+
+impl<F: Fn(&str) -> Option<usize>> MarginPattern for F {
+    fn prefix_len(&self, text: &str) -> Option<usize> { self(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed("<custom pattern>") }
+}
+
+
+/// A `MarginPattern` set by `TrimOptions::prefix_pattern`, which is shared by the clones of the options.
+///
+/// Two shared patterns are only equal if they are the same pattern.
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Clone)]
+pub(crate) struct SharedPattern(pub Arc<dyn MarginPattern + Send + Sync>);
+
+#[cfg(target_has_atomic = "ptr")]
+impl fmt::Debug for SharedPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SharedPattern").field(&self.0.description()).finish()
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl PartialEq for SharedPattern {
+    fn eq(&self, other: &SharedPattern) -> bool { Arc::ptr_eq(&self.0, &other.0) }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Eq for SharedPattern {}
+
+
+/// The margin prefix used while trimming: either a literal string or an arbitrary `MarginPattern`.
+///
+/// A `Shared` pattern is owned by the trimming, e.g., by a `MarginLines` created from a pattern passed by value.
+#[derive(Clone)]
+pub(crate) enum Margin<'p> {
+    Literal(&'p str),
+    Pattern(&'p dyn MarginPattern),
+    Shared(Rc<dyn MarginPattern + 'p>),
+}
+
+impl<'p> Margin<'p> {
+    /// Removes the margin prefix from the start of `text`.
+    pub fn strip<'a>(&self, text: &'a str) -> Option<&'a str> {
+        let len = match *self {
+            Margin::Literal(prefix) => return text.strip_prefix(prefix),
+            Margin::Pattern(pattern) => pattern.prefix_len(text),
+            Margin::Shared(ref pattern) => pattern.prefix_len(text),
+        };
+        len.and_then(|len| text.get(len..))
+    }
+
+    /// Describes the margin prefix in error messages.
+    pub fn description(&self) -> Cow<'_, str> {
+        match *self {
+            Margin::Literal(prefix) => Cow::Borrowed(prefix),
+            Margin::Pattern(pattern) => pattern.description(),
+            Margin::Shared(ref pattern) => pattern.description(),
+        }
+    }
+}
+
+impl<'p> From<&'p str> for Margin<'p> {
+    fn from(prefix: &'p str) -> Margin<'p> { Margin::Literal(prefix) }
+}
+
+impl<'p> fmt::Debug for Margin<'p> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Margin::Literal(prefix) => f.debug_tuple("Literal").field(&prefix).finish(),
+            Margin::Pattern(_) | Margin::Shared(_) => f.debug_tuple("Pattern").field(&self.description()).finish(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use galvanic_assert::matchers::*;
+    use super::*;
+    use MarginTrimmable;
+
+    fn strip<'a, P: MarginPattern>(pattern: &P, text: &'a str) -> Option<&'a str> {
+        Margin::Pattern(pattern).strip(text)
+    }
+
+    #[test]
+    fn should_match_literal_and_char_patterns() {
+        assert_that!(&strip(&"->", "->a"), eq(Some("a")));
+        assert_that!(&strip(&"->".to_string(), "-a"), eq(None));
+        assert_that!(&strip(&'│', "│a"), eq(Some("a")));
+        assert_that!(&strip(&['|', '¦'], "¦a"), eq(Some("a")));
+        assert_that!(&strip(&&['|', '¦'][..], "a|"), eq(None));
+    }
+
+    #[test]
+    fn should_match_owned_and_shared_strings() {
+        let prefix = "->";
+        let prefix_ref: &&str = &prefix;
+        assert_that!(&"\n  ->a\n".trim_margin_with(prefix_ref), eq(Some("a".to_string())));
+        assert_that!(&"\n  ->a\n".trim_margin_with(Cow::Borrowed(prefix)), eq(Some("a".to_string())));
+        assert_that!(&"\n  ->a\n".trim_margin_with(Cow::<str>::Owned(prefix.to_string())), eq(Some("a".to_string())));
+        assert_that!(&"\n  ->a\n".trim_margin_with(Box::<str>::from(prefix)), eq(Some("a".to_string())));
+        assert_that!(&"\n  ->a\n".trim_margin_with(Rc::<str>::from(prefix)), eq(Some("a".to_string())));
+        assert_that!(&"\n  ->a\n".trim_margin_with(Arc::<str>::from(prefix)), eq(Some("a".to_string())));
+        let error = "\n  ->a\n  b\n".try_trim_margin_with(Box::<str>::from(prefix)).unwrap_err();
+        assert_that!(&error.prefix(), eq("->"));
+    }
+
+    #[test]
+    fn should_reject_closure_length_inside_char() {
+        assert_that!(&strip(&|_: &str| Some(1), "¦a"), eq(None));
+        assert_that!(&strip(&|text: &str| text.find(']').map(|idx| idx + 1), "[12]a"), eq(Some("a")));
+    }
+
+    #[test]
+    fn should_describe_patterns() {
+        assert_that!(&'¦'.description().into_owned(), eq("¦".to_string()));
+        assert_that!(&['|', '¦'].description().into_owned(), eq("[|¦]".to_string()));
+    }
+}
@@ -0,0 +1,139 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Nested quote levels as in e-mails, e.g., `> > text`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use error::{ErrorKind, MarginError};
+use line_ending::LineBreaks;
+
+
+/// Returns the lines of `input` as `(number, offset, text, ending)` tuples.
+///
+/// Unlike `RawLines` no blank lines are skipped, but the empty rest after a final line break is not a line.
+fn split_lines(input: &str) -> impl Iterator<Item = (usize, usize, &str, &str)> {
+    let mut rest = Some(input);
+    let mut offset = 0;
+    let mut number = 0;
+    core::iter::from_fn(move || {
+        let (text, ending, next) = match LineBreaks::LfOrCrLf.split_line(rest?) {
+            Some((text, ending, next)) => (text, ending, Some(next).filter(|next| !next.is_empty())),
+            None => (rest?, "", None),
+        };
+        let line = (number + 1, offset, text, ending);
+        rest = next;
+        offset += text.len() + ending.len();
+        number += 1;
+        Some(line)
+    })
+}
+
+/// Parses up to `max_levels` quote levels, i.e., `prefix`es optionally preceded by spaces and tabs.
+///
+/// Returns the number of levels and the byte offset behind the last parsed `prefix`.
+fn parse_levels(text: &str, prefix: &str, max_levels: usize) -> (usize, usize) {
+    let mut levels = 0;
+    let mut end = 0;
+    while levels < max_levels && !prefix.is_empty() {
+        match text[end..].trim_start_matches([' ', '\t']).strip_prefix(prefix) {
+            Some(rest) => end = text.len() - rest.len(),
+            None => break,
+        }
+        levels += 1;
+    }
+    (levels, end)
+}
+
+/// Returns the quote depth of every line.
+pub(crate) fn quote_depths(input: &str, prefix: &str) -> Vec<usize> {
+    split_lines(input).map(|(_, _, text, _)| parse_levels(text, prefix, usize::MAX).0).collect()
+}
+
+/// Removes `levels` quote levels and a single following space from every line.
+pub(crate) fn strip_quote_levels(input: &str, levels: usize, prefix: &str) -> Result<String, MarginError> {
+    let mut stripped = String::with_capacity(input.len());
+    for (number, offset, text, ending) in split_lines(input) {
+        let (parsed, end) = parse_levels(text, prefix, levels);
+        if parsed < levels {
+            let column = text.len() - text[end..].trim_start_matches([' ', '\t']).len();
+            return Err(MarginError::new(ErrorKind::MissingPrefix, number, offset + column, column, text, prefix));
+        }
+        let content = &text[end..];
+        stripped.push_str(if levels == 0 { content } else { content.strip_prefix(' ').unwrap_or(content) });
+        stripped.push_str(ending);
+    }
+    Ok(stripped)
+}
+
+/// Adds a quote level to every line, separated from the line by a space unless the line is empty.
+pub(crate) fn add_quote_level(input: &str, prefix: &str) -> String {
+    let mut quoted = String::with_capacity(input.len() + 8 * (prefix.len() + 1));
+    for (_, _, text, ending) in split_lines(input) {
+        quoted.push_str(prefix);
+        if !text.is_empty() {
+            quoted.push(' ');
+            quoted.push_str(text);
+        }
+        quoted.push_str(ending);
+    }
+    quoted
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use galvanic_assert::matchers::*;
+    use proptest::prelude::*;
+    use super::*;
+
+    #[test]
+    fn should_report_quote_depth_of_every_line() {
+        let mail = "> > original\n>> also original\n> reply\n\nanswer\n";
+        assert_that!(&quote_depths(mail, ">"), eq(vec![2, 2, 1, 0, 0]));
+        assert_that!(&quote_depths("", ">"), eq(vec![0]));
+    }
+
+    #[test]
+    fn should_strip_levels_and_single_space() {
+        let mail = "> > original\r\n>>  indented\r\n> >\r\n";
+        assert_that!(&strip_quote_levels(mail, 1, ">"), eq(Ok("> original\r\n>  indented\r\n>\r\n".to_string())));
+        assert_that!(&strip_quote_levels(mail, 2, ">"), eq(Ok("original\r\n indented\r\n\r\n".to_string())));
+    }
+
+    #[test]
+    fn should_report_line_with_fewer_levels() {
+        let error = strip_quote_levels("> > a\n>  b\n", 2, ">").unwrap_err();
+        assert_that!(&error.line(), eq(2));
+        assert_that!(&error.column(), eq(3));
+        assert_that!(&error.offset(), eq(9));
+    }
+
+    #[test]
+    fn should_add_quote_level() {
+        assert_that!(&add_quote_level("> original\n\nreply", ">"), eq("> > original\n>\n> reply".to_string()));
+    }
+
+    proptest! {
+        #[test]
+        fn should_round_trip_through_quote_levels(text in "[a> \t\r\n]{0,40}") {
+            let quoted = add_quote_level(&text, ">");
+            let depths: Vec<_> = quote_depths(&text, ">").into_iter().map(|depth| depth + 1).collect();
+            prop_assert_eq!(quote_depths(&quoted, ">"), depths);
+            prop_assert_eq!(strip_quote_levels(&quoted, 1, ">"), Ok(text));
+        }
+    }
+}
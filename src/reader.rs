@@ -0,0 +1,266 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use alloc::string::String;
+use error::MarginError;
+use lines::{is_blank, strip_margin, RawLine};
+use options::Policy;
+use pattern::Margin;
+use std::io::{self, BufRead, Read};
+
+
+/// How far the input has been read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The first line has not been read yet.
+    Start,
+    /// The first line has been read and the input contains a line break.
+    Lines,
+    /// The input has been read completely or an error occurred.
+    Done,
+}
+
+/// Trims the lines of an input which is read line by line, e.g., by a reader.
+///
+/// Applies the same rules as `MarginTrimmable::try_trim_margin_with` while holding only the current line.
+#[derive(Debug)]
+pub(crate) struct LineTrimmer {
+    prefix: String,
+    policy: Policy,
+    state: State,
+    number: usize,
+    offset: usize,
+}
+
+impl LineTrimmer {
+    pub fn new(prefix: &str) -> LineTrimmer {
+        LineTrimmer { prefix: prefix.into(), policy: Policy::default(), state: State::Start, number: 0, offset: 0 }
+    }
+
+    /// Checks if the end of the input has been reached or an error occurred.
+    pub fn is_done(&self) -> bool { self.state == State::Done }
+
+    /// Ends the input, e.g., because reading its next line failed.
+    pub fn abort(&mut self) { self.state = State::Done; }
+
+    /// Ends the input because reading its next line failed and describes the failure as a `MarginError`.
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    pub fn io_error(&mut self, error: &io::Error) -> MarginError {
+        self.abort();
+        MarginError::io(self.number + 1, self.offset, error, &self.prefix)
+    }
+
+    /// Trims the next `line` of the input including its line break; an empty `line` marks the end of the input.
+    ///
+    /// # Returns
+    /// * The content of the line and its line break, or `None` if the line is skipped
+    /// * A `MarginError` if the line does not start with the margin prefix
+    pub fn trim_line<'l>(&mut self, line: &'l str) -> Result<Option<(&'l str, &'static str)>, MarginError> {
+        let ending = if line.ends_with("\r\n") {
+            "\r\n"
+        } else if line.ends_with('\n') {
+            "\n"
+        } else {
+            ""
+        };
+        let text = &line[..line.len() - ending.len()];
+        let raw = RawLine { number: self.number + 1, offset: self.offset, text, ending };
+        self.number += 1;
+        self.offset += line.len();
+
+        match self.state {
+            // strings without line break are not modified
+            State::Start if ending.is_empty() => {
+                self.state = State::Done;
+                return Ok(Some((text, "")));
+            },
+            State::Start => self.state = State::Lines,
+            State::Lines if ending.is_empty() => self.state = State::Done,
+            State::Lines | State::Done => {},
+        }
+        // a blank first and last line are skipped
+        if is_blank(text) && (raw.number == 1 || ending.is_empty()) {
+            return Ok(None);
+        }
+
+        match strip_margin(raw, &Margin::Literal(&self.prefix), None, &self.policy) {
+            Ok(content) => Ok(Some((content, ending))),
+            Err(fault) => {
+                self.state = State::Done;
+                Err(fault.into())
+            },
+        }
+    }
+}
+
+
+/// A reader which removes the margin of the text read from another reader on the fly.
+///
+/// The same rules as in `MarginTrimmable::try_trim_margin_with` apply, i.e., the output is identical
+/// to trimming the whole input at once, but only a single line of the input is held in memory.
+/// A line without margin prefix is reported as an `io::Error` of kind `InvalidData` wrapping the `MarginError`,
+/// whose `Display` implementation includes the line number. After an error the reader behaves as if at its end.
+///
+/// ```
+/// extern crate trim_margin;
+/// use std::io::Read;
+/// use trim_margin::MarginReader;
+///
+/// fn main() {
+///     let input = "
+///         |first line
+///         |second line
+///     ";
+///     let mut trimmed = String::new();
+///     MarginReader::new(input.as_bytes(), "|").read_to_string(&mut trimmed).unwrap();
+///     assert_eq!(trimmed, "first line\nsecond line");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MarginReader<R> {
+    inner: R,
+    trimmer: LineTrimmer,
+    line: String,
+    ending: Option<&'static str>,
+    output: String,
+    position: usize,
+}
+
+impl<R: BufRead> MarginReader<R> {
+    /// Creates a reader which removes blanks and the `margin_prefix` from the lines read from `inner`.
+    pub fn new<M: AsRef<str>>(inner: R, margin_prefix: M) -> MarginReader<R> {
+        MarginReader {
+            inner,
+            trimmer: LineTrimmer::new(margin_prefix.as_ref()),
+            line: String::new(),
+            ending: None,
+            output: String::new(),
+            position: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R { &self.inner }
+
+    /// Returns the underlying reader.
+    ///
+    /// Input which has been read but not been returned yet is lost.
+    pub fn into_inner(self) -> R { self.inner }
+
+    /// Reads the next line of the input and appends its trimmed content to the output.
+    fn read_next_line(&mut self) -> io::Result<()> {
+        self.line.clear();
+        if let Err(error) = self.inner.read_line(&mut self.line) {
+            self.trimmer.abort();
+            return Err(error);
+        }
+        let trimmed = self.trimmer.trim_line(&self.line)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if let Some((content, ending)) = trimmed {
+            self.output.extend(self.ending.take());
+            self.output.push_str(content);
+            self.ending = Some(ending).filter(|ending| !ending.is_empty());
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> BufRead for MarginReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position == self.output.len() {
+            self.output.clear();
+            self.position = 0;
+            while self.output.is_empty() && !self.trimmer.is_done() {
+                self.read_next_line()?;
+            }
+        }
+        Ok(&self.output.as_bytes()[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.output.len());
+    }
+}
+
+impl<R: BufRead> Read for MarginReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use galvanic_assert::matchers::*;
+    use proptest::prelude::*;
+    use std::io::BufReader;
+    use super::*;
+    use MarginTrimmable;
+
+    fn read_trimmed(input: &str, capacity: usize) -> io::Result<String> {
+        let mut trimmed = String::new();
+        MarginReader::new(BufReader::with_capacity(capacity, input.as_bytes()), "|").read_to_string(&mut trimmed)?;
+        Ok(trimmed)
+    }
+
+    #[test]
+    fn should_trim_lines_while_reading() {
+        let txt = "\r\n    |first\r\n    |  second\n  ";
+        assert_that!(&read_trimmed(txt, 2).unwrap(), eq("first\r\n  second".to_string()));
+    }
+
+    #[test]
+    fn should_yield_lines_by_read_line() {
+        let mut reader = MarginReader::new("\n  |first\n  |second\n".as_bytes(), "|");
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_that!(&line, eq("first\n".to_string()));
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_that!(&line, eq("second".to_string()));
+    }
+
+    #[test]
+    fn should_report_line_without_margin_as_io_error() {
+        let error = read_trimmed("\n  |first\n  second\n", 4).unwrap_err();
+        assert_that!(&error.kind(), eq(io::ErrorKind::InvalidData));
+        let margin_error = error.get_ref().and_then(|error| error.downcast_ref::<MarginError>()).unwrap();
+        assert_that!(&margin_error.line(), eq(3));
+        assert_that!(&error.to_string().starts_with("line 3: expected margin prefix"), eq(true));
+    }
+
+    #[test]
+    fn should_end_after_line_which_cannot_be_read() {
+        let mut reader = MarginReader::new(&b"\n |a\xff\n |b\n |c\n"[..], "|");
+        let mut buffer = [0; 16];
+        assert_that!(&reader.read(&mut buffer).unwrap_err().kind(), eq(io::ErrorKind::InvalidData));
+        assert_that!(&reader.read(&mut buffer).unwrap(), eq(0));
+    }
+
+    proptest! {
+        #[test]
+        fn should_read_like_trim_margin(text in "[ab |\t\r\n]{0,40}", capacity in 1usize..8) {
+            match text.try_trim_margin() {
+                Ok(trimmed) => prop_assert_eq!(read_trimmed(&text, capacity).ok(), Some(trimmed)),
+                Err(_) => prop_assert!(read_trimmed(&text, capacity).is_err()),
+            }
+        }
+    }
+}
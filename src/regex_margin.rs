@@ -0,0 +1,204 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Margins matched by regular expressions, e.g., line numbers or timestamps.
+
+use alloc::borrow::Cow;
+use error::{ErrorKind, Fault, MarginError};
+use lines::RawLines;
+use options::Policy;
+use pattern::{Margin, MarginPattern};
+use regex::{Captures, Regex};
+
+
+/// Matches `regex` at the start of `text`, i.e., a match starting later in the line is no margin.
+fn captures_at_start<'a>(regex: &Regex, text: &'a str) -> Option<Captures<'a>> {
+    regex.captures(text).filter(|captures| captures.get(0).is_some_and(|margin| margin.start() == 0))
+}
+
+/// A `Regex` matches the margin prefix if it matches at the start of a line after its leading blanks.
+///
+/// The whole match is removed, so the regex does not need to start with `^`.
+impl MarginPattern for Regex {
+    fn prefix_len(&self, text: &str) -> Option<usize> {
+        self.find(text).filter(|margin| margin.start() == 0).map(|margin| margin.end())
+    }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self.as_str()) }
+}
+
+impl MarginPattern for &Regex {
+    fn prefix_len(&self, text: &str) -> Option<usize> { (*self).prefix_len(text) }
+
+    fn description(&self) -> Cow<'_, str> { Cow::Borrowed(self.as_str()) }
+}
+
+
+/// A line whose margin was matched by a `Regex` together with the captures of the margin.
+///
+/// Created by iterating `MarginCaptures`.
+#[derive(Debug)]
+pub struct CapturedLine<'a> {
+    number: usize,
+    captures: Option<Captures<'a>>,
+    content: &'a str,
+}
+
+impl<'a> CapturedLine<'a> {
+    /// The 1-based number of the line in the input.
+    pub fn line(&self) -> usize { self.number }
+
+    /// The line with its margin removed and without line break.
+    pub fn content(&self) -> &'a str { self.content }
+
+    /// The captures of the margin; `None` for strings without line break, which are not trimmed.
+    pub fn captures(&self) -> Option<&Captures<'a>> { self.captures.as_ref() }
+
+    /// The text of the capture group `name` in the margin, if it participated in the match.
+    pub fn name(&self, name: &str) -> Option<&'a str> {
+        self.captures.as_ref()?.name(name).map(|group| group.as_str())
+    }
+
+    /// The text of the capture group with the index `idx` in the margin, where `0` is the whole margin prefix.
+    pub fn get(&self, idx: usize) -> Option<&'a str> {
+        self.captures.as_ref()?.get(idx).map(|group| group.as_str())
+    }
+}
+
+
+/// A lazy iterator over the lines with their margin matched by a `Regex` removed, yielding the captures as well.
+///
+/// Created by `MarginTrimmable::margin_captures`. The same rules as in `MarginTrimmable::trim_margin_with` apply:
+/// a blank first and last line are skipped and strings without line break are yielded unmodified.
+/// After the first line without margin an error is yielded and the iteration ends.
+///
+/// ```
+/// extern crate regex;
+/// extern crate trim_margin;
+/// use regex::Regex;
+/// use trim_margin::MarginTrimmable;
+///
+/// fn main() {
+///     let listing = "
+///         12| fn main() {
+///         13|     run();
+///     ";
+///     let margin = Regex::new(r"(?<number>\d+)\| ").unwrap();
+///     let lines: Vec<_> = listing.margin_captures(&margin)
+///         .map(|line| line.map(|line| (line.name("number").unwrap().to_string(), line.content())))
+///         .collect::<Result<_, _>>()
+///         .unwrap();
+///     assert_eq!(lines, vec![("12".to_string(), "fn main() {"), ("13".to_string(), "    run();")]);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarginCaptures<'a> {
+    verbatim: Option<&'a str>,
+    lines: Option<RawLines<'a>>,
+    regex: &'a Regex,
+    policy: Policy,
+}
+
+impl<'a> MarginCaptures<'a> {
+    pub(crate) fn new(input: &'a str, regex: &'a Regex, policy: &Policy) -> MarginCaptures<'a> {
+        let (verbatim, lines) = match RawLines::new(input, policy) {
+            Some(lines) => (None, Some(lines)),
+            None => (Some(input), None),
+        };
+        MarginCaptures { verbatim, lines, regex, policy: *policy }
+    }
+}
+
+impl<'a> Iterator for MarginCaptures<'a> {
+    type Item = Result<CapturedLine<'a>, MarginError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(verbatim) = self.verbatim.take() {
+            return Some(Ok(CapturedLine { number: 1, captures: None, content: verbatim }));
+        }
+
+        let line = self.lines.as_mut()?.next()?;
+        let text = self.policy.indentation.trim(line.text);
+        match captures_at_start(self.regex, text) {
+            Some(captures) => {
+                let content = &text[captures.get(0).map_or(0, |margin| margin.end())..];
+                Some(Ok(CapturedLine { number: line.number, captures: Some(captures), content }))
+            },
+            None => {
+                self.lines = None;
+                let column = line.text.len() - text.len();
+                let prefix = Margin::Pattern(self.regex);
+                Some(Err(Fault { kind: ErrorKind::MissingPrefix, line, column, prefix }.into()))
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+    use super::*;
+    use MarginTrimmable;
+
+    #[test]
+    fn should_remove_margin_matched_at_line_start() {
+        let log = "
+            [12:00:01] started
+            [12:00:02]   waiting
+        ";
+        let margin = Regex::new(r"\[\d\d:\d\d:\d\d\] ").unwrap();
+        assert_that!(&log.trim_margin_with(&margin), maybe_some(eq("started\n  waiting".to_string())));
+    }
+
+    #[test]
+    fn should_not_match_margin_inside_line() {
+        let txt = "\n  1| one\n  x 2| two\n";
+        let error = txt.try_trim_margin_with(Regex::new(r"\d+\| ").unwrap()).unwrap_err();
+        assert_that!(&error.line(), eq(3));
+        assert_that!(&error.column(), eq(2));
+        assert_that!(&error.prefix(), eq(r"\d+\| "));
+    }
+
+    #[test]
+    fn should_expose_captures_per_line() {
+        let listing = "\n  7| a\n  8|\n";
+        let margin = Regex::new(r"(\d+)\| ?").unwrap();
+        let lines: Vec<_> = listing.margin_captures(&margin)
+            .map(|line| line.map(|line| (line.line(), line.get(1), line.content())))
+            .collect();
+        assert_that!(&lines, eq(vec![Ok((2, Some("7"), "a")), Ok((3, Some("8"), ""))]));
+    }
+
+    #[test]
+    fn should_end_captures_after_line_without_margin() {
+        let margin = Regex::new(r"(\d+)\|").unwrap();
+        let mut lines = "\n  1|a\n  b\n  2|c\n".margin_captures(&margin);
+        assert_that!(&lines.next().map(|line| line.is_ok()), eq(Some(true)));
+        assert_that!(&lines.next().map(|line| line.unwrap_err().line()), eq(Some(3)));
+        assert_that!(&lines.next().is_none(), eq(true));
+    }
+
+    #[test]
+    fn should_yield_string_without_line_break_without_captures() {
+        let margin = Regex::new(r"\d+\|").unwrap();
+        let line = "1|a".margin_captures(&margin).next().unwrap().unwrap();
+        assert_that!(&line.content(), eq("1|a"));
+        assert_that!(&line.captures().is_none(), eq(true));
+    }
+}
@@ -0,0 +1,90 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A port of Scala's `StringOps.stripMargin` (Scala 2.13).
+
+use alloc::string::String;
+
+
+/// Checks if Scala considers `c` a blank in front of the margin character, i.e., a control character or space.
+fn is_scala_blank(c: char) -> bool {
+    c <= ' '
+}
+
+/// Splits off the first line of `text` including its line break (`\n`, `\r\n` or `\r`).
+fn split_line_with_separator(text: &str) -> (&str, &str) {
+    let end = match text.find(['\n', '\r']) {
+        Some(idx) if text[idx..].starts_with("\r\n") => idx + 2,
+        Some(idx) => idx + 1,
+        None => text.len(),
+    };
+    text.split_at(end)
+}
+
+/// Removes the blanks and the `margin_char` from the start of every line which starts with them.
+///
+/// Other lines are kept as they are and, unlike `trim_margin`, blank first and last lines are not removed.
+pub(crate) fn strip_margin(input: &str, margin_char: char) -> String {
+    let mut stripped = String::with_capacity(input.len());
+    let mut rest = input;
+    while !rest.is_empty() {
+        let (line, next) = split_line_with_separator(rest);
+        let content = line.trim_start_matches(is_scala_blank);
+        match content.strip_prefix(margin_char) {
+            Some(content) => stripped.push_str(content),
+            None => stripped.push_str(line),
+        }
+        rest = next;
+    }
+    stripped
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use galvanic_assert::matchers::*;
+    use super::*;
+
+    #[test]
+    fn should_conform_to_scala_strip_margin() {
+        let conformance = [
+            // examples from the Scala documentation
+            ("|Hello,\n  |world!", '|', "Hello,\nworld!"),
+            ("Hello,\n  |world!", '|', "Hello,\nworld!"),
+            ("  #Hello,\n  #world!", '#', "Hello,\nworld!"),
+            // lines without margin are left untouched
+            ("Hello,\n  world!", '|', "Hello,\n  world!"),
+            ("  |Hello,\n  world!", '|', "Hello,\n  world!"),
+            // blank first and last lines are kept
+            ("\n  |Hello\n  ", '|', "\nHello\n  "),
+            ("", '|', ""),
+            // only a single margin character is removed
+            ("  ||Hello", '|', "|Hello"),
+            ("  | Hello", '|', " Hello"),
+            // line separators are preserved
+            ("Hello,\r\n  |world!", '|', "Hello,\r\nworld!"),
+            ("Hello,\r  |world!", '|', "Hello,\rworld!"),
+            ("  |Hello,\n\n  |world!\n", '|', "Hello,\n\nworld!\n"),
+            // control characters count as blanks, non-ASCII whitespace does not
+            ("\u{c}|Hello", '|', "Hello"),
+            ("\t \u{b}|Hello", '|', "Hello"),
+            ("\u{a0}|Hello", '|', "\u{a0}|Hello"),
+        ];
+        for &(input, margin_char, expected) in conformance.iter() {
+            assert_that!(&strip_margin(input, margin_char), eq(expected.to_string()));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use core::fmt;
+
+
+/// A `fmt::Write` sink which fills a byte slice and fails once the slice is full.
+pub(crate) struct SliceWriter<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+}
+
+impl<'b> SliceWriter<'b> {
+    pub fn new(buffer: &'b mut [u8]) -> SliceWriter<'b> {
+        SliceWriter { buffer, len: 0 }
+    }
+
+    /// The number of bytes written so far.
+    pub fn len(&self) -> usize { self.len }
+}
+
+impl<'b> fmt::Write for SliceWriter<'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buffer.get_mut(self.len..end).ok_or(fmt::Error)?.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use galvanic_assert::matchers::*;
+    use core::fmt::Write;
+    use super::*;
+
+    #[test]
+    fn should_write_whole_strings_only() {
+        let mut buffer = [0u8; 5];
+        let mut writer = SliceWriter::new(&mut buffer);
+        assert_that!(&writer.write_str("abc"), eq(Ok(())));
+        assert_that!(&writer.write_str("def"), eq(Err(fmt::Error)));
+        assert_that!(&writer.write_str("d"), eq(Ok(())));
+        assert_that!(&writer.len(), eq(4));
+        assert_that!(&&buffer[..4], eq(&b"abcd"[..]));
+    }
+}
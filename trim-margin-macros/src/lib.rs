@@ -0,0 +1,133 @@
+/* Copyright 2018 Christopher Bacher
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compile-time companion of the `trim-margin` crate.
+//!
+//! The `trim_margin!` macro applies `MarginTrimmable::trim_margin_with` to a string literal while compiling.
+//! It expands to a new string literal, so the result is a `&'static str` which can be used in `const` and `static` items.
+//!
+//! ```
+//! extern crate trim_margin_macros;
+//! use trim_margin_macros::trim_margin;
+//!
+//! const USAGE: &str = trim_margin!("
+//!     |usage: tool [options]
+//!     |  -h  print this help
+//! ");
+//!
+//! const SCRIPT: &str = trim_margin!("
+//!     #echo hello
+//!     #exit 0
+//! ", prefix = "#");
+//!
+//! fn main() {
+//!     assert_eq!(USAGE, "usage: tool [options]\n  -h  print this help");
+//!     assert_eq!(SCRIPT, "echo hello\nexit 0");
+//! }
+//! ```
+//!
+//! A line without margin is reported as a compile error.
+//!
+//! ```compile_fail
+//! extern crate trim_margin_macros;
+//! use trim_margin_macros::trim_margin;
+//!
+//! const BROKEN: &str = trim_margin!("
+//!     |this line is fine,
+//!     but this one lacks the margin.
+//! ");
+//! # fn main() {}
+//! ```
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate quote;
+extern crate syn;
+extern crate trim_margin;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, LitStr, Token};
+use trim_margin::{MarginError, MarginTrimmable};
+
+
+/// The arguments of `trim_margin!`: a string literal optionally followed by `prefix = "..."`.
+struct TrimMarginInput {
+    text: LitStr,
+    prefix: Option<LitStr>,
+}
+
+impl Parse for TrimMarginInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let text = input.parse()?;
+        let mut prefix = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key != "prefix" {
+                return Err(syn::Error::new(key.span(), "expected `prefix`"));
+            }
+            input.parse::<Token![=]>()?;
+            prefix = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(TrimMarginInput { text, prefix })
+    }
+}
+
+/// Removes the margin of a string literal at compile time.
+///
+/// `trim_margin!("...")` uses `|` as margin prefix, `trim_margin!("...", prefix = "#")` a custom one.
+/// The trimming rules are the ones of `MarginTrimmable::trim_margin_with`.
+/// If a line lacks the margin prefix a compile error pointing at that line is emitted.
+#[proc_macro]
+pub fn trim_margin(input: TokenStream) -> TokenStream {
+    let TrimMarginInput { text, prefix } = parse_macro_input!(input as TrimMarginInput);
+    let prefix = prefix.map_or_else(|| "|".to_string(), |p| p.value());
+
+    match text.value().try_trim_margin_with(&prefix) {
+        Ok(trimmed) => {
+            let trimmed = LitStr::new(&trimmed, text.span());
+            quote!(#trimmed).into()
+        },
+        Err(error) => {
+            // `syn::Error::to_compile_error` refers to `::core`, which 2015 edition crates cannot resolve
+            let message = error.to_string();
+            quote_spanned!(line_span(&text, &error)=> compile_error!(#message)).into()
+        },
+    }
+}
+
+/// Determines the span of the offending line inside the literal.
+///
+/// Falls back to the span of the whole literal if the compiler cannot provide sub-spans
+/// or if escape sequences make source and value positions diverge.
+fn line_span(text: &LitStr, error: &MarginError) -> Span {
+    let source = text.token().to_string();
+    let value = text.value();
+    let value_start = if source.starts_with('r') {
+        source.find('"').map(|quote| quote + 1)
+    } else {
+        Some(1)
+    };
+
+    let line_start = error.offset() - error.column();
+    let line_end = line_start + error.text().len();
+    value_start
+        .filter(|&start| source.get(start..start + value.len()) == Some(value.as_str()))
+        .and_then(|start| text.token().subspan(start + line_start..start + line_end))
+        .unwrap_or_else(|| text.span())
+}
@@ -0,0 +1,47 @@
+#[macro_use] extern crate galvanic_assert;
+extern crate trim_margin;
+extern crate trim_margin_macros;
+
+use galvanic_assert::matchers::*;
+use trim_margin::MarginTrimmable;
+use trim_margin_macros::trim_margin;
+
+const MULTILINE: &str = trim_margin!("
+    |this
+    |  is a
+    |  multiline string
+");
+
+static SCRIPT: &str = trim_margin!("
+    #echo hello
+    #exit 0
+", prefix = "#");
+
+#[test]
+fn should_be_usable_in_const_items() {
+    assert_that!(&MULTILINE, eq("this\n  is a\n  multiline string"));
+}
+
+#[test]
+fn should_allow_arbitrary_margin_prefix() {
+    assert_that!(&SCRIPT, eq("echo hello\nexit 0"));
+}
+
+#[test]
+fn should_not_modify_single_line_string() {
+    assert_that!(&trim_margin!("|hello, world"), eq("|hello, world"));
+}
+
+#[test]
+fn should_agree_with_runtime_trimming() {
+    let txt = r#"
+        |"quoted"
+        |    indented \n not an escape
+        |
+    "#;
+    assert_that!(&trim_margin!(r#"
+        |"quoted"
+        |    indented \n not an escape
+        |
+    "#, prefix = "|",).to_string(), eq(txt.trim_margin().unwrap()));
+}